@@ -0,0 +1,79 @@
+use std::process::Command;
+
+use serde_json::Value;
+
+/// Runs `program` with `args`, returning what it prints. Failing to do so
+/// fails the build, as the versions it tells are required.
+fn run(program: &str, args: &[&str]) -> String {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .unwrap_or_else(|error| panic!("`{} {}` couldn't run: {}", program, args.join(" "), error));
+    if !output.status.success() {
+        panic!(
+            "`{} {}` failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    String::from_utf8(output.stdout)
+        .unwrap_or_else(|_| panic!("`{} {}` printed invalid UTF-8", program, args.join(" ")))
+}
+
+/// Version of the `phosphorus_core` package resolved for the application,
+/// as told by `cargo metadata`
+fn core_version() -> Option<String> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| String::from("cargo"));
+    let manifest = format!("{}/Cargo.toml", std::env::var("CARGO_MANIFEST_DIR").ok()?);
+    let metadata = run(
+        &cargo,
+        &[
+            "metadata",
+            "--format-version",
+            "1",
+            "--offline",
+            "--manifest-path",
+            &manifest,
+        ],
+    );
+    let metadata: Value = serde_json::from_str(&metadata).ok()?;
+
+    // The dependency of the application, rather than any package with that name
+    let resolve = &metadata["resolve"];
+    let root = resolve["root"].as_str()?;
+    let node = resolve["nodes"]
+        .as_array()?
+        .iter()
+        .find(|node| node["id"].as_str() == Some(root))?;
+    let deps = node["deps"].as_array()?;
+    metadata["packages"]
+        .as_array()?
+        .iter()
+        .filter(|package| package["name"].as_str() == Some("phosphorus_core"))
+        .find(|package| deps.iter().any(|dep| dep["pkg"] == package["id"]))?["version"]
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Exposes the compiler and `phosphorus_core` versions the application is
+/// built with, so that plugins built differently can be refused. The build
+/// fails if either can't be found, as an empty version would let any
+/// plugin declaring none through.
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let rustc_version = run(&rustc, &["--version"]).trim().to_string();
+    if rustc_version.is_empty() {
+        panic!("`{} --version` printed nothing", rustc);
+    }
+    println!("cargo:rustc-env=PHOSPHORUS_RUSTC_VERSION={}", rustc_version);
+
+    let core_version = core_version()
+        .filter(|version| !version.is_empty())
+        .expect("the version of phosphorus_core couldn't be found with `cargo metadata`");
+    println!("cargo:rustc-env=PHOSPHORUS_CORE_VERSION={}", core_version);
+
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
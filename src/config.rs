@@ -1,10 +1,24 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 const BASE: &'static str = ".phosphorus";
 const DATA: &'static str = "songs_meta";
 const CACHE: &'static str = "cache";
 const DOWNLOAD: &'static str = "download";
 const PLAYLISTS: &'static str = "playlists_meta";
+const SETTINGS: &'static str = "settings";
+const PLUGINS: &'static str = "plugins";
+const HISTORY: &'static str = "search_history";
+
+/// Volume used when no preference has been saved yet
+const DEFAULT_VOLUME: u8 = 100;
+
+/// Number of downloads served at the same time when no preference
+/// has been saved yet
+const DEFAULT_CONCURRENT_DOWNLOADS: usize = 2;
+
+/// Seconds after which a search is abandoned, when no preference
+/// has been saved yet
+const DEFAULT_SEARCH_TIMEOUT: u64 = 15;
 
 #[derive(Debug)]
 pub enum ConfigError {
@@ -91,6 +105,230 @@ impl Paths {
     pub fn playlists_as_str(&self) -> &str {
         &self.playlists.to_str().unwrap()
     }
+
+    /// Path of the file holding user settings
+    pub fn settings(&self) -> PathBuf {
+        self.base.join(SETTINGS)
+    }
+
+    /// Path of the folder plugins are loaded from
+    pub fn plugins(&self) -> PathBuf {
+        self.base.join(PLUGINS)
+    }
+
+    /// Path of the file holding the queries sent so far
+    pub fn search_history(&self) -> PathBuf {
+        self.base.join(HISTORY)
+    }
+}
+
+/// Tells what to do when the reproduction queue runs out of songs
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum RepeatMode {
+    /// The reproduction stops
+    Off,
+    /// The queue is filled again with the same playlist
+    All,
+    /// The last song is reproduced again, forever
+    One,
+}
+
+impl RepeatMode {
+    /// Returns the mode that follows `self` when cycling through them
+    pub fn next(&self) -> Self {
+        match self {
+            RepeatMode::Off => RepeatMode::All,
+            RepeatMode::All => RepeatMode::One,
+            RepeatMode::One => RepeatMode::Off,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            RepeatMode::Off => "off",
+            RepeatMode::All => "all",
+            RepeatMode::One => "one",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(RepeatMode::Off),
+            "all" => Some(RepeatMode::All),
+            "one" => Some(RepeatMode::One),
+            _ => None,
+        }
+    }
+}
+
+/// User preferences that must survive restarts. They are stored
+/// as `key = value` lines in the file returned by `Paths::settings`.
+pub struct Settings {
+    path: PathBuf,
+    /// Volume level, as a percentage
+    volume: u8,
+    /// Tells whether songs queued from a playlist are shuffled
+    shuffle: bool,
+    repeat: RepeatMode,
+    /// Number of downloads that can be served at the same time
+    concurrent_downloads: usize,
+    /// Seconds after which a search is abandoned
+    search_timeout: u64,
+    /// Names of the plugins to register. Empty means all the built-in ones
+    plugins: Vec<String>,
+    /// Name of the plugin serving the queries, if the user has chosen one
+    default_plugin: Option<String>,
+    /// Tells whether queries are served by all the plugins at once
+    federated_search: bool,
+}
+
+impl Settings {
+    /// Loads settings from disk. Missing or malformed values are
+    /// replaced by their defaults.
+    pub fn load(paths: &Paths) -> Self {
+        let mut settings = Settings {
+            path: paths.settings(),
+            volume: DEFAULT_VOLUME,
+            shuffle: false,
+            repeat: RepeatMode::Off,
+            concurrent_downloads: DEFAULT_CONCURRENT_DOWNLOADS,
+            search_timeout: DEFAULT_SEARCH_TIMEOUT,
+            plugins: vec![],
+            default_plugin: None,
+            federated_search: false,
+        };
+
+        let content = std::fs::read_to_string(&settings.path).unwrap_or_default();
+        for line in content.lines() {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            match key {
+                "volume" => {
+                    if let Ok(volume) = value.parse::<u8>() {
+                        settings.volume = volume.min(100);
+                    }
+                }
+                "shuffle" => {
+                    if let Ok(shuffle) = value.parse::<bool>() {
+                        settings.shuffle = shuffle;
+                    }
+                }
+                "repeat" => {
+                    if let Some(repeat) = RepeatMode::parse(value) {
+                        settings.repeat = repeat;
+                    }
+                }
+                "concurrent_downloads" => {
+                    if let Ok(count) = value.parse::<usize>() {
+                        settings.concurrent_downloads = count.max(1);
+                    }
+                }
+                "search_timeout" => {
+                    if let Ok(secs) = value.parse::<u64>() {
+                        settings.search_timeout = secs.max(1);
+                    }
+                }
+                "plugins" => {
+                    settings.plugins = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(String::from)
+                        .collect();
+                }
+                "default_plugin" if !value.is_empty() => {
+                    settings.default_plugin = Some(value.to_string());
+                }
+                "federated_search" => {
+                    if let Ok(federated) = value.parse::<bool>() {
+                        settings.federated_search = federated;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        settings
+    }
+
+    /// Writes settings to disk
+    pub fn save(&self) -> std::io::Result<()> {
+        let content = format!(
+            concat!(
+                "volume = {}\n",
+                "shuffle = {}\n",
+                "repeat = {}\n",
+                "concurrent_downloads = {}\n",
+                "search_timeout = {}\n",
+                "plugins = {}\n",
+                "default_plugin = {}\n",
+                "federated_search = {}\n",
+            ),
+            self.volume,
+            self.shuffle,
+            self.repeat.as_str(),
+            self.concurrent_downloads,
+            self.search_timeout,
+            self.plugins.join(", "),
+            self.default_plugin.as_deref().unwrap_or_default(),
+            self.federated_search
+        );
+        std::fs::write(&self.path, content)
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(100);
+    }
+
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+    }
+
+    pub fn repeat(&self) -> RepeatMode {
+        self.repeat
+    }
+
+    pub fn set_repeat(&mut self, repeat: RepeatMode) {
+        self.repeat = repeat;
+    }
+
+    pub fn concurrent_downloads(&self) -> usize {
+        self.concurrent_downloads
+    }
+
+    pub fn search_timeout(&self) -> Duration {
+        Duration::from_secs(self.search_timeout)
+    }
+
+    pub fn plugins(&self) -> &[String] {
+        &self.plugins
+    }
+
+    pub fn default_plugin(&self) -> Option<&str> {
+        self.default_plugin.as_deref()
+    }
+
+    pub fn set_default_plugin(&mut self, name: String) {
+        self.default_plugin = Some(name);
+    }
+
+    pub fn federated_search(&self) -> bool {
+        self.federated_search
+    }
+
+    pub fn set_federated_search(&mut self, federated: bool) {
+        self.federated_search = federated;
+    }
 }
 
 /// Configures the environment creating the necessary folders.
@@ -120,6 +358,8 @@ pub fn config_env() -> Result<Paths, ConfigError> {
     let playlists = base.join(PLAYLISTS);
     check_folder(&playlists, PLAYLISTS)?;
 
+    check_folder(&base.join(PLUGINS), PLUGINS)?;
+
     Ok(Paths::new(base, data, cache, download, playlists))
 }
 
@@ -8,6 +8,7 @@ use ui::Model;
 use crate::ui::Id;
 
 mod config;
+mod playback_history;
 mod player;
 mod ui;
 
@@ -46,14 +47,16 @@ fn main() {
     let model = Model::new(paths, playlist_manager, queue_manager);
     let mut model = match model {
         Ok(model) => model,
-        Err(_) => {
-            eprintln!("An error occured while trying to create the querier");
+        Err(msg) => {
+            eprintln!("An error occured while trying to start the application");
+            eprintln!("{}", msg);
             std::process::exit(1);
         }
     };
     // Enter alternate screen
     let _ = model.terminal.enter_alternate_screen();
     let _ = model.terminal.enable_raw_mode();
+    let _ = model.terminal.enable_mouse_capture();
 
     while !model.quit {
         // Tick
@@ -89,6 +92,7 @@ fn main() {
     }
 
     // Terminate terminal
+    let _ = model.terminal.disable_mouse_capture();
     let _ = model.terminal.leave_alternate_screen();
     let _ = model.terminal.disable_raw_mode();
 }
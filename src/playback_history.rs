@@ -0,0 +1,35 @@
+use phosphorus_core::song::Song;
+use std::collections::VecDeque;
+
+/// Keeps track of the songs that have been reproduced, from the
+/// oldest to the most recent. Once `capacity` songs are stored,
+/// pushing a new one discards the oldest.
+pub struct PlaybackHistory {
+    songs: VecDeque<Song>,
+    capacity: usize,
+}
+
+impl PlaybackHistory {
+    pub fn new(capacity: usize) -> Self {
+        PlaybackHistory {
+            songs: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Records `song` as the most recently reproduced
+    pub fn push(&mut self, song: Song) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.songs.len() == self.capacity {
+            self.songs.pop_front();
+        }
+        self.songs.push_back(song);
+    }
+
+    /// Removes and returns the most recently reproduced song
+    pub fn pop(&mut self) -> Option<Song> {
+        self.songs.pop_back()
+    }
+}
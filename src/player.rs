@@ -1,69 +1,329 @@
 use phosphorus_core::song::Song;
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
 use std::{
     error::Error,
     fs::File,
     io::BufReader,
-    sync::mpsc::{SendError, Sender},
+    sync::{
+        mpsc::{Receiver, RecvTimeoutError, SendError, Sender},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
 
+use crate::ui::event::UserEvent;
+
+/// How often the engine checks the sink when no command arrives
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 pub enum Command {
+    /// Replaces the current song and starts reproducing it
+    Load(Song),
+    /// Resumes a paused reproduction
     Play,
+    /// Pauses the current reproduction
     Pause,
+    /// Stops the current reproduction, dropping the song
+    Stop,
+    /// Moves the reproduction to the given position
+    Seek(Duration),
+    /// Moves the reproduction forward by the given amount
+    SeekForward(Duration),
+    /// Moves the reproduction backward by the given amount
+    SeekBackward(Duration),
+    /// Moves the reproduction to the given fraction of the song,
+    /// where `0.0` is the beginning and `1.0` the end
+    SeekTo(f64),
+    /// Sets the volume, where `1.0` is the unfiltered input
+    Volume(f32),
+    /// Terminates the engine
     Quit,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum State {
     None,
     Playing,
     Paused,
 }
 
+/// Reproduction position of the current song. It's kept up to date
+/// by the engine and can be cloned and read from any thread.
+#[derive(Clone, Default)]
+pub struct Position(Arc<Mutex<Duration>>);
+
+impl Position {
+    pub fn get(&self) -> Duration {
+        *self.0.lock().unwrap()
+    }
+
+    fn set(&self, position: Duration) {
+        *self.0.lock().unwrap() = position;
+    }
+}
+
+/// Errors that can occur while trying to reproduce a song
+#[derive(Debug)]
+pub enum PlayerError {
+    /// The song file couldn't be opened
+    FileError(String, String),
+    /// The song file couldn't be decoded
+    DecodeError(String, String),
+    /// The current song couldn't be moved to the requested position
+    SeekError(String),
+}
+
+impl std::fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerError::FileError(path, msg) => {
+                write!(f, "File `{}` couldn't be opened: {}", path, msg)
+            }
+            PlayerError::DecodeError(path, msg) => {
+                write!(f, "File `{}` couldn't be decoded: {}", path, msg)
+            }
+            PlayerError::SeekError(msg) => write!(f, "Seek failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PlayerError {}
+
+/// Interface to the audio engine. `Player` spawns an internal thread
+/// that owns the audio output and serves the `Command`s sent through
+/// its methods, so callers never block on audio.
+///
+/// State changes of the engine (song started, paused, ended, errors)
+/// are reported as `UserEvent`s through the channel provided in `try_new`.
 pub struct Player {
-    _stream: (OutputStream, OutputStreamHandle),
-    sink: Sink,
+    /// Sending half of the communication channel with the engine.
     commands_sender: Sender<Command>,
+    position: Position,
 }
 
 impl Player {
-    pub fn try_new() -> Result<Player, Box<dyn Error>> {
-        let (stream, stream_handle) = OutputStream::try_default()?;
-        let sink = Sink::try_new(&stream_handle)?;
-
+    pub fn try_new(user_event: Sender<UserEvent>) -> Result<Player, Box<dyn Error>> {
         let (tx, rx) = std::sync::mpsc::channel();
-        Ok(Player {
-            _stream: (stream, stream_handle),
-            sink,
-            commands_sender: tx,
-        })
+
+        // Channel used to check the correct setup of the engine. The audio
+        // output can't be moved between threads, so it has to be created
+        // by the engine itself, which then reports the outcome.
+        let (tmp_tx, tmp_rx) = std::sync::mpsc::channel();
+
+        let position = Position::default();
+        let engine_position = position.clone();
+
+        let _thread = std::thread::spawn(move || {
+            let engine = match Engine::try_new(user_event, engine_position) {
+                Ok(engine) => engine,
+                Err(msg) => {
+                    let _ = tmp_tx.send(Err(msg.to_string()));
+                    return;
+                }
+            };
+
+            let _ = tmp_tx.send(Ok(()));
+            engine.run(rx);
+        });
+
+        match tmp_rx.recv() {
+            Ok(Ok(())) => Ok(Player {
+                commands_sender: tx,
+                position,
+            }),
+            Ok(Err(msg)) => Err(msg.into()),
+            Err(err) => Err(Box::new(err)),
+        }
     }
 
+    /// Asks the engine to replace the current song with `song`
+    /// and start reproducing it.
     pub fn initiate(&self, song: &Song) -> Result<(), SendError<Command>> {
-        Ok(())
+        self.commands_sender.send(Command::Load(song.clone()))
     }
 
-    pub fn append(&self, source: Decoder<BufReader<File>>) -> Result<(), SendError<Command>> {
-        self.sink.append(source);
-        //self.play()?;
-        Ok(())
+    pub fn play(&self) -> Result<(), SendError<Command>> {
+        self.commands_sender.send(Command::Play)
     }
 
-    pub fn play(&self) -> Result<(), SendError<Command>> {
-        self.sink.play();
-        //self.commands_sender.send(Command::Play)?;
-        Ok(())
+    pub fn pause(&self) -> Result<(), SendError<Command>> {
+        self.commands_sender.send(Command::Pause)
+    }
+
+    pub fn stop(&self) -> Result<(), SendError<Command>> {
+        self.commands_sender.send(Command::Stop)
+    }
+
+    pub fn seek(&self, position: Duration) -> Result<(), SendError<Command>> {
+        self.commands_sender.send(Command::Seek(position))
+    }
+
+    pub fn seek_forward(&self, step: Duration) -> Result<(), SendError<Command>> {
+        self.commands_sender.send(Command::SeekForward(step))
     }
 
-    pub fn _pause(&self) -> Result<(), Box<dyn Error>> {
-        self.sink.pause();
-        //self.commands_sender.send(Command::Pause)?;
-        Ok(())
+    pub fn seek_backward(&self, step: Duration) -> Result<(), SendError<Command>> {
+        self.commands_sender.send(Command::SeekBackward(step))
+    }
+
+    pub fn seek_to(&self, ratio: f64) -> Result<(), SendError<Command>> {
+        self.commands_sender.send(Command::SeekTo(ratio))
+    }
+
+    pub fn set_volume(&self, volume: f32) -> Result<(), SendError<Command>> {
+        self.commands_sender.send(Command::Volume(volume))
+    }
+
+    /// Returns a handle to the reproduction position of the current song
+    pub fn position(&self) -> Position {
+        self.position.clone()
     }
 }
 
 impl Drop for Player {
     fn drop(&mut self) {
         let _result = self.commands_sender.send(Command::Quit);
+    }
+}
+
+/// Owns the audio output and executes the commands sent by `Player`
+struct Engine {
+    _stream: (OutputStream, OutputStreamHandle),
+    sink: Sink,
+    state: State,
+    position: Position,
+    /// Duration of the current song, if known
+    duration: Option<Duration>,
+    user_event: Sender<UserEvent>,
+}
+
+impl Engine {
+    fn try_new(user_event: Sender<UserEvent>, position: Position) -> Result<Self, Box<dyn Error>> {
+        let (stream, stream_handle) = OutputStream::try_default()?;
+        let sink = Sink::try_new(&stream_handle)?;
+
+        Ok(Engine {
+            _stream: (stream, stream_handle),
+            sink,
+            state: State::None,
+            position,
+            duration: None,
+            user_event,
+        })
+    }
+
+    fn run(mut self, rx: Receiver<Command>) {
+        loop {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(Command::Quit) | Err(RecvTimeoutError::Disconnected) => break,
+                Ok(command) => self.execute(command),
+                Err(RecvTimeoutError::Timeout) => (),
+            }
+
+            // A song that was being played and left the sink
+            // empty has reached its end
+            if self.state == State::Playing && self.sink.empty() {
+                self.state = State::None;
+                self.position.set(Duration::ZERO);
+                let _ = self.user_event.send(UserEvent::TrackEnded);
+            } else if self.state != State::None {
+                self.position.set(self.sink.get_pos());
+            }
+        }
+
         self.sink.stop();
     }
+
+    fn execute(&mut self, command: Command) {
+        match command {
+            Command::Load(song) => match decode(song.path()) {
+                Ok(source) => {
+                    self.duration = song
+                        .details()
+                        .duration()
+                        .cloned()
+                        .or(source.total_duration());
+
+                    // Stopping the sink drops the current source. The next
+                    // append waits for the queue to flush and then resumes.
+                    self.sink.stop();
+                    self.sink.append(source);
+                    self.sink.play();
+                    self.state = State::Playing;
+                    self.position.set(Duration::ZERO);
+                    let _ = self.user_event.send(UserEvent::PlaySong(song));
+                }
+                Err(error) => {
+                    let _ = self
+                        .user_event
+                        .send(UserEvent::PlayError(error.to_string()));
+                }
+            },
+            Command::Play => {
+                if self.state == State::Paused {
+                    self.sink.play();
+                    self.state = State::Playing;
+                    let _ = self.user_event.send(UserEvent::PlaybackResumed);
+                }
+            }
+            Command::Pause => {
+                if self.state == State::Playing {
+                    self.sink.pause();
+                    self.state = State::Paused;
+                    let _ = self.user_event.send(UserEvent::PlaybackPaused);
+                }
+            }
+            Command::Stop => {
+                if self.state != State::None {
+                    self.sink.stop();
+                    self.state = State::None;
+                    self.position.set(Duration::ZERO);
+                    let _ = self.user_event.send(UserEvent::PlaybackStopped);
+                }
+            }
+            Command::Seek(position) => self.seek(position),
+            Command::SeekForward(step) => self.seek(self.sink.get_pos() + step),
+            Command::SeekBackward(step) => self.seek(self.sink.get_pos().saturating_sub(step)),
+            Command::SeekTo(ratio) => {
+                if let Some(duration) = self.duration {
+                    self.seek(duration.mul_f64(ratio.clamp(0.0, 1.0)));
+                }
+            }
+            Command::Volume(volume) => self.sink.set_volume(volume),
+            Command::Quit => (),
+        }
+    }
+
+    fn seek(&mut self, mut position: Duration) {
+        if self.state == State::None {
+            return;
+        }
+
+        if let Some(duration) = self.duration {
+            position = position.min(duration);
+        }
+
+        match self.sink.try_seek(position) {
+            Ok(_) => self.position.set(position),
+            Err(error) => {
+                let error = PlayerError::SeekError(error.to_string());
+                let _ = self
+                    .user_event
+                    .send(UserEvent::PlayError(error.to_string()));
+            }
+        }
+    }
+}
+
+/// Opens the file at `path` and builds a decoder over it
+fn decode(path: &str) -> Result<Decoder<BufReader<File>>, PlayerError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(PlayerError::FileError(path.into(), err.to_string())),
+    };
+
+    match Decoder::new(BufReader::new(file)) {
+        Ok(source) => Ok(source),
+        Err(err) => Err(PlayerError::DecodeError(path.into(), err.to_string())),
+    }
 }
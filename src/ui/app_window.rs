@@ -1,20 +1,33 @@
-use phosphorus_core::{playlist_manager::PlaylistManager, queue::QueueManager, song::Song};
+use phosphorus_core::{
+    playlist_manager::{Playlist, PlaylistManager},
+    queue::QueueManager,
+    song::Song,
+};
+use rand::seq::SliceRandom;
+use std::time::Duration;
 
-use phosphorus_core::plugin_manager::query::QueryResult;
 use tui_realm_stdlib::Container;
 use tuirealm::{
     command::{Cmd, Direction as CDir, Position},
     event::{Key, KeyEvent, KeyModifiers},
-    props::{BorderSides, Borders, Layout},
+    props::{Alignment, BorderSides, Borders, Layout},
     tui::layout::{Constraint, Direction},
     AttrValue, Attribute, Component, Event, MockComponent, State, StateValue,
 };
 
+use crate::{config::RepeatMode, playback_history::PlaybackHistory};
+
 use super::{
+    download_manager::Downloads,
     event::UserEvent,
+    notification::Notifications,
     playlist_list::PlaylistList,
+    plugin_registry::SearchResult,
     queue::Queue,
-    secondary_window::{HelpWindow, PlaylistWindow, ResultsWindow},
+    secondary_window::{
+        DownloadsWindow, HelpWindow, NotificationsWindow, PlaylistWindow, PluginsWindow,
+        ResultsView, ResultsWindow,
+    },
     welcome_window::WelcomWindow,
     AppMsg,
 };
@@ -23,12 +36,23 @@ const PLAYLIST_LIST: usize = 0;
 const MAIN_WINDOW: usize = 1;
 const QUEUE: usize = 2;
 
+/// Number of reproduced songs remembered for `Previous`
+const HISTORY_CAPACITY: usize = 50;
+
+/// Step used by `,` and `.` to move inside the current song
+const SHORT_SEEK: Duration = Duration::from_secs(5);
+/// Step used by `<` and `>` to move inside the current song
+const LONG_SEEK: Duration = Duration::from_secs(30);
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum MainWindowType {
     Welcome,
     Help,
     PlaylistSongs,
     Results,
+    Downloads,
+    Notifications,
+    Plugins,
 }
 
 impl MainWindowType {
@@ -38,6 +62,9 @@ impl MainWindowType {
             MainWindowType::Help => true,
             MainWindowType::PlaylistSongs => true,
             MainWindowType::Results => true,
+            MainWindowType::Downloads => true,
+            MainWindowType::Notifications => true,
+            MainWindowType::Plugins => true,
         }
     }
 
@@ -47,6 +74,9 @@ impl MainWindowType {
             MainWindowType::Help => true,
             MainWindowType::PlaylistSongs => true,
             MainWindowType::Results => false,
+            MainWindowType::Downloads => true,
+            MainWindowType::Notifications => true,
+            MainWindowType::Plugins => true,
         }
     }
 
@@ -64,6 +94,9 @@ impl MainWindowType {
             MainWindowType::Help => true,
             MainWindowType::PlaylistSongs => true,
             MainWindowType::Results => true,
+            MainWindowType::Downloads => true,
+            MainWindowType::Notifications => true,
+            MainWindowType::Plugins => true,
         }
     }
 }
@@ -77,11 +110,40 @@ pub struct AppWindow {
     playlist_manager: PlaylistManager,
     active_playlist: Option<usize>,
     queue_manager: QueueManager,
-    current_result: Option<QueryResult>,
+    current_result: Option<Vec<SearchResult>>,
+    /// Filter and sort applied to the results shown
+    results_view: ResultsView,
+    /// Tells whether further results can be requested for the shown ones
+    more_results: bool,
+    /// Songs reproduced before the current one
+    history: PlaybackHistory,
+    /// Song currently loaded in the player
+    current_song: Option<Song>,
+    /// Playlist the queue has been filled from, if any
+    queue_source: Option<usize>,
+    shuffle: bool,
+    /// Songs of the queue in the order they had before being shuffled,
+    /// restored once shuffle is turned off
+    unshuffled: Vec<Song>,
+    repeat: RepeatMode,
+    /// Downloads registered so far, shown by the downloads window
+    downloads: Downloads,
+    /// Notifications raised so far, shown by the notifications window
+    notifications: Notifications,
+    /// Names of the registered plugins, shown by the plugins window
+    plugins: Vec<String>,
+    /// Plugin currently serving the queries
+    active_plugin: Option<String>,
 }
 
 impl AppWindow {
-    pub fn new(playlist_manager: PlaylistManager, queue_manager: QueueManager) -> Self {
+    pub fn new(
+        playlist_manager: PlaylistManager,
+        queue_manager: QueueManager,
+        downloads: Downloads,
+        notifications: Notifications,
+        plugins: Vec<String>,
+    ) -> Self {
         let children: Vec<Box<dyn MockComponent>> = vec![
             PlaylistList::default()
                 .list(
@@ -119,8 +181,40 @@ impl AppWindow {
             queue_manager,
             active_playlist: None,
             current_result: None,
+            results_view: ResultsView::default(),
+            more_results: false,
+            history: PlaybackHistory::new(HISTORY_CAPACITY),
+            current_song: None,
+            queue_source: None,
+            shuffle: false,
+            unshuffled: vec![],
+            repeat: RepeatMode::Off,
+            downloads,
+            notifications,
+            plugins,
+            active_plugin: None,
         }
     }
+
+    /// Shows `component` as the secondary window of the given `kind`,
+    /// remembering the window to go back to once it's closed. The focus
+    /// moves to the new window, as the component focused so far would
+    /// otherwise keep being drawn as focused.
+    fn open_secondary(&mut self, kind: MainWindowType, mut component: Box<dyn MockComponent>) {
+        if self.main_window_type.is_secondary() {
+            self.previous_window = Some(MainWindowType::Welcome);
+        } else {
+            self.previous_window = Some(self.main_window_type);
+        }
+        self.main_window_type = kind;
+        if let Some(child) = self.component.children.get_mut(self.active) {
+            child.attr(Attribute::Focus, AttrValue::Flag(false));
+        }
+        component.attr(Attribute::Focus, AttrValue::Flag(true));
+        self.component.children.remove(MAIN_WINDOW);
+        self.component.children.insert(MAIN_WINDOW, component);
+        self.active = MAIN_WINDOW;
+    }
 }
 
 impl Component<AppMsg, UserEvent> for AppWindow {
@@ -132,19 +226,90 @@ impl Component<AppMsg, UserEvent> for AppWindow {
         }) = ev
         {
             if self.main_window_type != MainWindowType::Help {
-                if self.main_window_type.is_secondary() {
-                    self.previous_window = Some(MainWindowType::Welcome);
-                } else {
-                    self.previous_window = Some(self.main_window_type);
-                }
-                self.main_window_type = MainWindowType::Help;
-                self.component.children.remove(MAIN_WINDOW);
+                let window = MainWindowType::Help.default().unwrap();
+                self.open_secondary(MainWindowType::Help, window);
+                return Some(AppMsg::ShowHelp);
+            }
+        }
+
+        // Event for downloads window opening
+        if let Event::Keyboard(KeyEvent {
+            code: Key::Char('d'),
+            modifiers: KeyModifiers::CONTROL,
+        }) = ev
+        {
+            if self.main_window_type != MainWindowType::Downloads {
+                let window = DownloadsWindow::new(&self.downloads.entries()).boxed();
+                self.open_secondary(MainWindowType::Downloads, window);
+                return Some(AppMsg::ShowDownloads);
+            }
+        }
+
+        // Event for notifications window opening
+        if let Event::Keyboard(KeyEvent {
+            code: Key::Char('n'),
+            modifiers: KeyModifiers::CONTROL,
+        }) = ev
+        {
+            if self.main_window_type != MainWindowType::Notifications {
+                let window = NotificationsWindow::new(&self.notifications.entries()).boxed();
+                self.open_secondary(MainWindowType::Notifications, window);
+                return Some(AppMsg::ShowNotifications);
+            }
+        }
+
+        // Event for plugins window opening
+        if let Event::Keyboard(KeyEvent {
+            code: Key::Char('p'),
+            modifiers: KeyModifiers::CONTROL,
+        }) = ev
+        {
+            if self.main_window_type != MainWindowType::Plugins {
+                let window =
+                    PluginsWindow::new(&self.plugins, self.active_plugin.as_deref()).boxed();
+                self.open_secondary(MainWindowType::Plugins, window);
+                return Some(AppMsg::ShowPlugins);
+            }
+        }
+
+        // Event for search mode switching
+        if let Event::Keyboard(KeyEvent {
+            code: Key::Char('f'),
+            modifiers: KeyModifiers::CONTROL,
+        }) = ev
+        {
+            return Some(AppMsg::ToggleFederatedSearch);
+        }
+
+        // Keeps track of the plugin serving the queries
+        if let Event::User(UserEvent::DefaultPluginChanged(name)) = ev {
+            self.active_plugin = Some(name);
+            if self.main_window_type == MainWindowType::Plugins {
+                let table = PluginsWindow::table(&self.plugins, self.active_plugin.as_deref());
                 self.component
                     .children
-                    .insert(MAIN_WINDOW, self.main_window_type.default().unwrap());
-                self.active = MAIN_WINDOW;
-                return Some(AppMsg::ShowHelp);
+                    .get_mut(MAIN_WINDOW)
+                    .unwrap()
+                    .attr(Attribute::Content, AttrValue::Table(table));
             }
+            return Some(AppMsg::None);
+        }
+
+        // Keeps the downloads and notifications windows up to date
+        if let Event::Tick = ev {
+            let table = match self.main_window_type {
+                MainWindowType::Downloads => DownloadsWindow::table(&self.downloads.entries()),
+                MainWindowType::Notifications => {
+                    NotificationsWindow::table(&self.notifications.entries())
+                }
+                _ => return None,
+            };
+            self.component
+                .children
+                .get_mut(MAIN_WINDOW)
+                .unwrap()
+                .attr(Attribute::Content, AttrValue::Table(table));
+            return Some(AppMsg::None);
         }
 
         // Event for query results arrival
@@ -155,17 +320,73 @@ impl Component<AppMsg, UserEvent> for AppWindow {
                 self.previous_window = Some(self.main_window_type);
             }
             self.main_window_type = MainWindowType::Results;
+            // The sort is kept across searches, the filter is not
+            self.results_view.clear_filter();
             self.component.children.remove(MAIN_WINDOW);
-            self.component
-                .children
-                .insert(MAIN_WINDOW, ResultsWindow::new(&result).boxed());
+            self.component.children.insert(
+                MAIN_WINDOW,
+                ResultsWindow::new(&result, &self.results_view).boxed(),
+            );
             self.active = MAIN_WINDOW;
             self.current_result = Some(result);
+            self.more_results = true;
+            return Some(AppMsg::None);
+        }
+
+        // Once the results are exhausted, no more are requested
+        if let Event::User(UserEvent::NoMoreResults) = ev {
+            self.more_results = false;
+            return None;
+        }
+
+        // Event for more results of the same query, appended to the shown ones
+        if let Event::User(UserEvent::QueryResultExtended(more)) = ev {
+            let result = self.current_result.get_or_insert_with(Vec::new);
+            result.extend(more);
+            if self.main_window_type == MainWindowType::Results {
+                refresh_results(result, &self.results_view, &mut self.component.children);
+            }
             return Some(AppMsg::None);
         }
 
         let index = self.active;
         let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
+
+        // While the filter is being typed, keys edit it
+        let filtering = MAIN_WINDOW == self.active
+            && MainWindowType::Results == self.main_window_type
+            && self.results_view.editing;
+        if filtering {
+            let edited = match &ev {
+                Event::Keyboard(KeyEvent {
+                    code: Key::Char(ch),
+                    modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                }) => {
+                    self.results_view.push(*ch);
+                    true
+                }
+                Event::Keyboard(KeyEvent {
+                    code: Key::Backspace,
+                    ..
+                }) => {
+                    self.results_view.pop();
+                    true
+                }
+                Event::Keyboard(KeyEvent {
+                    code: Key::Enter, ..
+                }) => {
+                    self.results_view.editing = false;
+                    true
+                }
+                _ => false,
+            };
+            if edited {
+                let result = self.current_result.as_deref().unwrap_or_default();
+                refresh_results(result, &self.results_view, children);
+                return Some(AppMsg::None);
+            }
+        }
+
         let mut child: &mut Box<dyn MockComponent> = children.get_mut(index).unwrap();
         child.attr(Attribute::Focus, AttrValue::Flag(true));
 
@@ -186,11 +407,93 @@ impl Component<AppMsg, UserEvent> for AppWindow {
                 }
                 return Some(AppMsg::ResetFocus);
             }
-            Event::User(UserEvent::DownloadFinished(song)) => {
-                self.playlist_manager
-                    .add_to(song, crate::playlist_manager::ALL_SONGS);
+            Event::User(UserEvent::PlaySong(song)) => {
+                if let Some(previous) = self.current_song.take() {
+                    self.history.push(previous);
+                }
+                self.current_song = Some(song);
                 return Some(AppMsg::None);
             }
+            Event::User(UserEvent::QueueModesChanged { shuffle, repeat }) => {
+                if shuffle && !self.shuffle {
+                    self.unshuffled = shuffle_queue(&mut self.queue_manager);
+                    rebuild_queue(&self.queue_manager, children);
+                } else if !shuffle && self.shuffle {
+                    unshuffle_queue(&mut self.queue_manager, &self.unshuffled);
+                    self.unshuffled.clear();
+                    rebuild_queue(&self.queue_manager, children);
+                }
+                self.shuffle = shuffle;
+                self.repeat = repeat;
+                return Some(AppMsg::None);
+            }
+            Event::User(UserEvent::TrackEnded)
+                if self.repeat == RepeatMode::One && self.current_song.is_some() =>
+            {
+                // The song is taken out, so that it isn't recorded in the history
+                // when reproduced again, whether or not playback starts
+                return Some(AppMsg::Play(self.current_song.take().unwrap()));
+            }
+            Event::User(UserEvent::TrackEnded) | Event::User(UserEvent::NextRequested) => {
+                // Advances to the next song in the queue, if any
+                let mut next = self.queue_manager.pop();
+                if next.is_none() && self.repeat == RepeatMode::All {
+                    let source = self
+                        .queue_source
+                        .and_then(|index| self.playlist_manager.playlists().get(index));
+                    if let Some(playlist) = source {
+                        next = refill_queue(&mut self.queue_manager, playlist, self.shuffle);
+                        if self.shuffle {
+                            self.unshuffled = playlist.songs().to_vec();
+                        }
+                    }
+                }
+
+                if let Some(song) = next {
+                    rebuild_queue(&self.queue_manager, children);
+                    return Some(AppMsg::Play(song));
+                }
+                return Some(AppMsg::None);
+            }
+            Event::User(UserEvent::PreviousRequested) => {
+                match self.history.pop() {
+                    Some(song) => {
+                        // The current song comes next again, rather than being
+                        // recorded in the history, whether or not playback starts
+                        if let Some(current) = self.current_song.take() {
+                            queue_front(&mut self.queue_manager, current);
+                            rebuild_queue(&self.queue_manager, children);
+                        }
+                        return Some(AppMsg::Play(song));
+                    }
+                    // Nothing to go back to, so the current song restarts
+                    None if self.current_song.is_some() => {
+                        return Some(AppMsg::Seek(Duration::ZERO))
+                    }
+                    None => return Some(AppMsg::None),
+                }
+            }
+            Event::User(UserEvent::QueueRequested { playlist, songs }) => {
+                self.queue_manager.clear();
+                for song in songs {
+                    self.queue_manager.push(song);
+                }
+                if self.shuffle {
+                    self.unshuffled = shuffle_queue(&mut self.queue_manager);
+                }
+                self.queue_source = Some(playlist);
+                rebuild_queue(&self.queue_manager, children);
+                return Some(AppMsg::None);
+            }
+            Event::User(UserEvent::DownloadFinished(song)) => {
+                // A song missing from the library can still be played, so the failure is only reported
+                let error = self
+                    .playlist_manager
+                    .add_to(song.clone(), phosphorus_core::playlist_manager::ALL_SONGS)
+                    .err()
+                    .map(|error| error.to_string());
+                return Some(AppMsg::DownloadCompleted(song, error));
+            }
             Event::Keyboard(KeyEvent { code: Key::Tab, .. }) => {
                 // Removes focus from current active component
                 child.attr(Attribute::Focus, AttrValue::Flag(false));
@@ -240,41 +543,49 @@ impl Component<AppMsg, UserEvent> for AppWindow {
                             self.previous_window = Some(self.main_window_type);
                         }
                         self.main_window_type = MainWindowType::PlaylistSongs;
+                        child.attr(Attribute::Focus, AttrValue::Flag(false));
+                        let mut window = PlaylistWindow::new(playlist, index).boxed();
+                        window.attr(Attribute::Focus, AttrValue::Flag(true));
                         children.remove(MAIN_WINDOW);
-                        children.insert(MAIN_WINDOW, PlaylistWindow::new(playlist).boxed());
+                        children.insert(MAIN_WINDOW, window);
                         self.active = MAIN_WINDOW;
                         return Some(AppMsg::ShowPlaylist);
                     }
                 }
                 MAIN_WINDOW => match self.main_window_type {
                     MainWindowType::PlaylistSongs => {
-                        if let State::One(StateValue::Usize(index)) = child.state() {
-                            let playlist = self
-                                .playlist_manager
-                                .playlists()
-                                .get(self.active_playlist.unwrap())
-                                .unwrap();
-
-                            if index >= playlist.songs().len() {
-                                return Some(AppMsg::MissingSong);
-                            }
-                            self.queue_manager.set_on_playlist(playlist, index);
-                            rebuild_queue(&self.queue_manager, children);
-                            return Some(AppMsg::Play(
-                                playlist.songs().get(index).unwrap().clone(),
-                            ));
+                        let active = self.active_playlist.and_then(|active| {
+                            Some((active, self.playlist_manager.playlists().get(active)?))
+                        });
+                        if let (Some((active, playlist)), State::One(StateValue::Usize(index))) =
+                            (active, child.state())
+                        {
+                            return Some(AppMsg::PlayFromPlaylist {
+                                playlist: active,
+                                songs: playlist.songs().to_vec(),
+                                index,
+                            });
                         }
                     }
                     MainWindowType::Results => {
                         if let State::One(StateValue::Usize(index)) = child.state() {
                             if let Some(result) = &self.current_result {
-                                if let Some(song_data) = result.data().get(index) {
+                                if let Some(song_data) = self.results_view.get(result, index) {
                                     self.queue_manager.clear();
-                                    return Some(AppMsg::DownloadSong(song_data.clone()));
+                                    self.queue_source = None;
+                                    rebuild_queue(&self.queue_manager, children);
+                                    return Some(AppMsg::PlayFromResult(song_data.clone()));
                                 }
                             }
                         }
                     }
+                    MainWindowType::Plugins => {
+                        if let State::One(StateValue::Usize(index)) = child.state() {
+                            if let Some(name) = self.plugins.get(index) {
+                                return Some(AppMsg::SetDefaultPlugin(name.clone()));
+                            }
+                        }
+                    }
                     _ => {}
                 },
                 QUEUE => {}
@@ -305,6 +616,83 @@ impl Component<AppMsg, UserEvent> for AppWindow {
                     }
                 }
             }
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('d'),
+                modifiers: KeyModifiers::NONE,
+            }) => {
+                if MAIN_WINDOW == self.active && MainWindowType::Results == self.main_window_type {
+                    if let State::One(StateValue::Usize(index)) = child.state() {
+                        if let Some(result) = &self.current_result {
+                            if let Some(song_data) = self.results_view.get(result, index) {
+                                return Some(AppMsg::DownloadSong(song_data.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+            // Filter and sort of the results
+            Event::Keyboard(KeyEvent {
+                code: Key::Char(key @ ('/' | 'o' | 'O')),
+                ..
+            }) if MAIN_WINDOW == self.active
+                && MainWindowType::Results == self.main_window_type =>
+            {
+                match key {
+                    '/' => self.results_view.editing = true,
+                    'o' => self.results_view.cycle_sort(),
+                    _ => self.results_view.reverse(),
+                }
+                let result = self.current_result.as_deref().unwrap_or_default();
+                refresh_results(result, &self.results_view, children);
+                return Some(AppMsg::None);
+            }
+            Event::Keyboard(KeyEvent {
+                code: Key::Backspace,
+                ..
+            }) if MAIN_WINDOW == self.active
+                && MainWindowType::Results == self.main_window_type =>
+            {
+                self.results_view.clear_filter();
+                let result = self.current_result.as_deref().unwrap_or_default();
+                refresh_results(result, &self.results_view, children);
+                return Some(AppMsg::None);
+            }
+            // Reaching the end of the results asks for more
+            Event::Keyboard(KeyEvent { code: Key::End, .. })
+            | Event::Keyboard(KeyEvent {
+                code: Key::Char('l'),
+                modifiers: KeyModifiers::NONE,
+            }) if MAIN_WINDOW == self.active
+                && MainWindowType::Results == self.main_window_type =>
+            {
+                child.perform(Cmd::GoTo(Position::End));
+                if !self.more_results {
+                    return Some(AppMsg::None);
+                }
+                return Some(AppMsg::LoadMoreResults);
+            }
+            Event::Keyboard(KeyEvent {
+                code: Key::Delete, ..
+            }) => {
+                if MAIN_WINDOW == self.active && MainWindowType::Downloads == self.main_window_type
+                {
+                    if let State::One(StateValue::Usize(index)) = child.state() {
+                        return Some(AppMsg::CancelDownload(index));
+                    }
+                }
+            }
+            // Retries the selected download, instead of changing the repeat mode
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('r'),
+                modifiers: KeyModifiers::NONE,
+            }) if MAIN_WINDOW == self.active
+                && MainWindowType::Downloads == self.main_window_type =>
+            {
+                if let State::One(StateValue::Usize(index)) = child.state() {
+                    return Some(AppMsg::RetryDownload(index));
+                }
+                return Some(AppMsg::None);
+            }
             Event::Keyboard(KeyEvent {
                 code: Key::Char('-'),
                 ..
@@ -321,6 +709,62 @@ impl Component<AppMsg, UserEvent> for AppWindow {
                 code: Key::Char(' '),
                 modifiers: KeyModifiers::CONTROL,
             }) => return Some(AppMsg::PlayPause),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('s'),
+                modifiers: KeyModifiers::CONTROL,
+            }) => return Some(AppMsg::Stop),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('.'),
+                ..
+            }) => return Some(AppMsg::SeekForward(SHORT_SEEK)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char(','),
+                ..
+            }) => return Some(AppMsg::SeekBackward(SHORT_SEEK)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('>'),
+                ..
+            }) => return Some(AppMsg::SeekForward(LONG_SEEK)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('<'),
+                ..
+            }) => return Some(AppMsg::SeekBackward(LONG_SEEK)),
+            Event::Keyboard(KeyEvent {
+                code: Key::Up,
+                modifiers: KeyModifiers::CONTROL,
+            }) => return Some(AppMsg::VolumeUp),
+            Event::Keyboard(KeyEvent {
+                code: Key::Down,
+                modifiers: KeyModifiers::CONTROL,
+            }) => return Some(AppMsg::VolumeDown),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('m'),
+                modifiers: KeyModifiers::NONE,
+            }) => return Some(AppMsg::ToggleMute),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('s'),
+                modifiers: KeyModifiers::NONE,
+            }) => return Some(AppMsg::ToggleShuffle),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('r'),
+                modifiers: KeyModifiers::NONE,
+            }) => return Some(AppMsg::CycleRepeat),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('n'),
+                modifiers: KeyModifiers::NONE,
+            }) => return Some(AppMsg::Next),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('p'),
+                modifiers: KeyModifiers::NONE,
+            }) => return Some(AppMsg::Previous),
+            Event::Keyboard(KeyEvent {
+                code: Key::Char(digit @ '0'..='9'),
+                modifiers: KeyModifiers::NONE,
+            }) => {
+                // Digits jump to the corresponding tenth of the song
+                let tenths = digit.to_digit(10).unwrap();
+                return Some(AppMsg::SeekTo(tenths as f64 / 10.0));
+            }
             _ => (),
         };
 
@@ -359,7 +803,171 @@ fn table_events(
     Some(AppMsg::None)
 }
 
+/// Shows `results` in the results window, as filtered and sorted by `view`
+fn refresh_results(
+    results: &[SearchResult],
+    view: &ResultsView,
+    children: &mut [Box<dyn MockComponent>],
+) {
+    let window = children.get_mut(MAIN_WINDOW).unwrap();
+    window.attr(
+        Attribute::Content,
+        AttrValue::Table(ResultsWindow::table(results, view)),
+    );
+    window.attr(
+        Attribute::Title,
+        AttrValue::Title((view.title(), Alignment::Left)),
+    );
+}
+
 fn rebuild_queue(queue: &QueueManager, children: &mut Vec<Box<dyn MockComponent>>) {
     children.remove(QUEUE);
     children.insert(QUEUE, Queue::default().list(queue.details()).boxed());
 }
+
+/// Reorders randomly the songs in `queue`. Every song is still
+/// reproduced exactly once before the queue runs out. Returns the
+/// songs in the order they had before.
+fn shuffle_queue(queue: &mut QueueManager) -> Vec<Song> {
+    let mut songs = vec![];
+    while let Some(song) = queue.pop() {
+        songs.push(song);
+    }
+    let order = songs.clone();
+    songs.shuffle(&mut rand::thread_rng());
+    for song in songs {
+        queue.push(song);
+    }
+    order
+}
+
+/// Puts the songs left in `queue` back in the order they have in `order`.
+/// Songs missing from `order`, as those queued after shuffling, follow
+/// in the order they have in the queue.
+fn unshuffle_queue(queue: &mut QueueManager, order: &[Song]) {
+    let mut pending = vec![];
+    while let Some(song) = queue.pop() {
+        pending.push(song);
+    }
+    for song in order {
+        if let Some(index) = pending.iter().position(|left| left.path() == song.path()) {
+            queue.push(pending.remove(index));
+        }
+    }
+    for song in pending {
+        queue.push(song);
+    }
+}
+
+/// Puts `song` before the songs left in `queue`
+fn queue_front(queue: &mut QueueManager, song: Song) {
+    let mut pending = vec![song];
+    while let Some(left) = queue.pop() {
+        pending.push(left);
+    }
+    for song in pending {
+        queue.push(song);
+    }
+}
+
+/// Fills `queue` again with all the songs in `playlist`, returning
+/// the one that has to be reproduced first
+fn refill_queue(queue: &mut QueueManager, playlist: &Playlist, shuffle: bool) -> Option<Song> {
+    if shuffle {
+        queue.clear();
+        for song in playlist.songs() {
+            queue.push(song.clone());
+        }
+        shuffle_queue(queue);
+        queue.pop()
+    } else {
+        queue.set_on_playlist(playlist, 0);
+        playlist.songs().first().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use phosphorus_core::song::SongDetails;
+
+    use super::*;
+
+    fn song(name: &str) -> Song {
+        Song::new(
+            &format!("{}.mp3", name),
+            &format!("{}.json", name),
+            SongDetails::new(name, None, None, None),
+        )
+    }
+
+    fn queue(songs: &[Song]) -> QueueManager {
+        let mut queue = QueueManager::default();
+        for song in songs {
+            queue.push(song.clone());
+        }
+        queue
+    }
+
+    /// Empties `queue`, returning its songs in the order they'd be played
+    fn drain(queue: &mut QueueManager) -> Vec<Song> {
+        let mut songs = vec![];
+        while let Some(song) = queue.pop() {
+            songs.push(song);
+        }
+        songs
+    }
+
+    fn songs(count: usize) -> Vec<Song> {
+        (0..count).map(|index| song(&index.to_string())).collect()
+    }
+
+    #[test]
+    fn shuffling_keeps_every_song_once() {
+        let songs = songs(20);
+        let mut queue = queue(&songs);
+
+        let order = shuffle_queue(&mut queue);
+        assert_eq!(order, songs);
+
+        let mut shuffled = drain(&mut queue);
+        assert_eq!(shuffled.len(), songs.len());
+        shuffled.sort_by(|a, b| a.path().cmp(b.path()));
+        let mut expected = songs.clone();
+        expected.sort_by(|a, b| a.path().cmp(b.path()));
+        assert_eq!(shuffled, expected);
+    }
+
+    #[test]
+    fn unshuffling_restores_the_order_of_the_songs_left() {
+        let songs = songs(10);
+        let mut queue = queue(&songs);
+        let order = shuffle_queue(&mut queue);
+
+        // Some songs are played, and another one is queued
+        let played: Vec<Song> = (0..3).filter_map(|_| queue.pop()).collect();
+        queue.push(song("late"));
+        unshuffle_queue(&mut queue, &order);
+
+        let mut expected: Vec<Song> = songs
+            .into_iter()
+            .filter(|song| !played.contains(song))
+            .collect();
+        expected.push(song("late"));
+        assert_eq!(drain(&mut queue), expected);
+    }
+
+    #[test]
+    fn queues_a_song_before_the_others() {
+        let songs = songs(3);
+        let mut queue = queue(&songs);
+        queue_front(&mut queue, song("first"));
+
+        let mut expected = vec![song("first")];
+        expected.extend(songs);
+        assert_eq!(drain(&mut queue), expected);
+
+        let mut empty = QueueManager::default();
+        queue_front(&mut empty, song("only"));
+        assert_eq!(drain(&mut empty), vec![song("only")]);
+    }
+}
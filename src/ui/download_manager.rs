@@ -0,0 +1,598 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use phosphorus_core::{plugin_manager::downloader::ProgressFollowerFn, song::Song};
+
+use super::{
+    event::UserEvent,
+    plugin_registry::{self, Track},
+};
+
+/// Maximum number of downloads that can be served at the same time
+pub const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+/// Number of times a download is attempted before being marked as failed
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Waiting time before the first retry. It doubles at every new attempt
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+
+/// How often a running download checks whether it has been cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Folder, inside the cache, where downloads are written until they complete
+const PARTIAL: &str = "partial";
+
+/// File describing the download a partial folder belongs to
+const MANIFEST: &str = "manifest";
+
+/// Download served by each worker, indexed by worker. A progress follower
+/// is a plain function, so every worker uses its own follower, which finds
+/// here the download to update.
+static PROGRESS: [Mutex<Option<(usize, Arc<Shared>)>>; MAX_CONCURRENT_DOWNLOADS] =
+    [const { Mutex::new(None) }; MAX_CONCURRENT_DOWNLOADS];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadState {
+    /// Waiting for a free worker
+    Queued,
+    /// Being downloaded, with the given completion percentage
+    Running(f32),
+    /// Failed, and waiting to be attempted again at the given instant
+    Retrying(Instant),
+    /// Failed for the given reason, and won't be attempted again
+    Failed(String),
+    /// Cancelled by the user
+    Cancelled,
+    Done,
+}
+
+impl DownloadState {
+    /// Tells whether the download is still going to be served
+    fn is_active(&self) -> bool {
+        matches!(
+            self,
+            DownloadState::Queued | DownloadState::Running(_) | DownloadState::Retrying(_)
+        )
+    }
+}
+
+impl std::fmt::Display for DownloadState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadState::Queued => write!(f, "Queued"),
+            DownloadState::Running(perc) => write!(f, "Downloading {:.2}%", perc),
+            DownloadState::Retrying(at) => write!(
+                f,
+                "Retrying in {}s",
+                at.saturating_duration_since(Instant::now()).as_secs()
+            ),
+            DownloadState::Failed(msg) => write!(f, "Failed: {}", msg),
+            DownloadState::Cancelled => write!(f, "Cancelled"),
+            DownloadState::Done => write!(f, "Done"),
+        }
+    }
+}
+
+/// Everything needed to download a song, and to download it
+/// again if it gets interrupted
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadRequest {
+    pub name: String,
+    pub artist: String,
+    pub duration: Duration,
+    pub url: String,
+    /// Name of the plugin serving the download. Empty means the default one
+    pub plugin: String,
+}
+
+impl DownloadRequest {
+    /// Request for a track found by `plugin`, which will serve the download
+    pub fn new(plugin: &str, track: &Track) -> Self {
+        DownloadRequest {
+            name: track.name.clone(),
+            artist: track.artist.clone(),
+            duration: track.duration,
+            url: track.url.clone(),
+            plugin: plugin.to_string(),
+        }
+    }
+
+    /// Name of the downloaded file, without extension
+    pub fn file_name(&self) -> String {
+        phosphorus_core::file_name_from_basics(&self.name, &self.artist)
+    }
+}
+
+/// Writes the manifest of a download in its partial folder. `target`
+/// is the path the file will be moved to once complete.
+fn write_manifest(dir: &Path, request: &DownloadRequest, target: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let content = format!(
+        "name = {}\nartist = {}\nduration = {}\nurl = {}\nplugin = {}\ntarget = {}\n",
+        request.name,
+        request.artist,
+        request.duration.as_millis(),
+        request.url,
+        request.plugin,
+        target
+    );
+    std::fs::write(dir.join(MANIFEST), content)
+}
+
+/// Reads the manifest of a partial folder. Returns `None` if
+/// the manifest is missing or incomplete. Manifests without
+/// a plugin are served by the default one.
+fn read_manifest(dir: &Path) -> Option<(DownloadRequest, PathBuf)> {
+    let content = std::fs::read_to_string(dir.join(MANIFEST)).ok()?;
+    let (mut name, mut artist, mut duration, mut url, mut target) = (None, None, None, None, None);
+    let mut plugin = String::new();
+    for line in content.lines() {
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim().to_string()),
+            None => continue,
+        };
+        match key {
+            "name" => name = Some(value),
+            "artist" => artist = Some(value),
+            "duration" => duration = value.parse::<u64>().ok().map(Duration::from_millis),
+            "url" => url = Some(value),
+            "plugin" => plugin = value,
+            "target" => target = Some(PathBuf::from(value)),
+            _ => (),
+        }
+    }
+
+    let request = DownloadRequest {
+        name: name?,
+        artist: artist?,
+        duration: duration?,
+        url: url?,
+        plugin,
+    };
+    Some((request, target?))
+}
+
+/// Removes everything but the manifest from a partial folder
+fn clear_partial(dir: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.file_name() == Some(OsStr::new(MANIFEST)) {
+            continue;
+        }
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)?;
+        } else {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves the file downloaded to `file`, inside a partial folder, to `target`,
+/// then removes the folder. Plugins may name the file differently, as when
+/// adding an extension, in which case the only other file in the folder is
+/// taken, the folder being cleared before every attempt.
+fn finalize(dir: &Path, file: &Path, target: &Path) -> Result<(), String> {
+    let file = if file.is_file() {
+        file.to_path_buf()
+    } else {
+        let entries = std::fs::read_dir(dir).map_err(|error| error.to_string())?;
+        let files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.file_name() != Some(OsStr::new(MANIFEST)))
+            .collect();
+        match &files[..] {
+            [file] => file.clone(),
+            [] => return Err(String::from("the downloaded file is missing")),
+            _ => return Err(String::from("the downloaded file can't be told apart")),
+        }
+    };
+
+    std::fs::rename(&file, target).map_err(|error| error.to_string())?;
+    let _ = std::fs::remove_dir_all(dir);
+    Ok(())
+}
+
+/// A snapshot of a download, as shown to the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadEntry {
+    pub name: String,
+    pub state: DownloadState,
+}
+
+struct DownloadItem {
+    request: DownloadRequest,
+    song: Song,
+    /// Folder the download is written to until it completes
+    dir: PathBuf,
+    state: DownloadState,
+    attempts: u32,
+    cancel: Arc<AtomicBool>,
+}
+
+/// State shared between the manager, its workers and the interface
+struct Shared {
+    /// Every download registered so far. A download is identified
+    /// by its index in this list
+    items: Mutex<Vec<DownloadItem>>,
+    /// Folder holding the partial folders of all the downloads
+    partial: PathBuf,
+    /// Signals workers that a download may be ready to be served
+    available: Condvar,
+    quit: AtomicBool,
+    user_event: Sender<UserEvent>,
+}
+
+/// A job picked by a worker
+struct Job {
+    id: usize,
+    url: String,
+    plugin: String,
+    dir: PathBuf,
+    /// Path, inside `dir`, the file is downloaded to
+    path: String,
+    target: PathBuf,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Shared {
+    /// Blocks until a download is ready to be served, marking it as
+    /// running. Returns `None` once the manager is shutting down.
+    fn next_job(&self) -> Option<Job> {
+        let mut items = self.items.lock().unwrap();
+        loop {
+            if self.quit.load(Ordering::SeqCst) {
+                return None;
+            }
+
+            let now = Instant::now();
+            let mut next_retry: Option<Instant> = None;
+            for (id, item) in items.iter_mut().enumerate() {
+                match item.state {
+                    DownloadState::Retrying(at) if at > now => {
+                        next_retry = Some(next_retry.map_or(at, |next| next.min(at)));
+                    }
+                    DownloadState::Queued | DownloadState::Retrying(_) => {
+                        item.state = DownloadState::Running(0.0);
+                        item.attempts += 1;
+                        item.cancel.store(false, Ordering::SeqCst);
+                        let path = item.dir.join(item.request.file_name());
+                        return Some(Job {
+                            id,
+                            url: item.request.url.clone(),
+                            plugin: item.request.plugin.clone(),
+                            dir: item.dir.clone(),
+                            path: path.to_str().unwrap().to_string(),
+                            target: PathBuf::from(item.song.path()),
+                            cancel: item.cancel.clone(),
+                        });
+                    }
+                    _ => (),
+                }
+            }
+
+            // Sleeps until something changes or the closest retry is due
+            items = match next_retry {
+                Some(at) => {
+                    let timeout = at.saturating_duration_since(now);
+                    self.available.wait_timeout(items, timeout).unwrap().0
+                }
+                None => self.available.wait(items).unwrap(),
+            };
+        }
+    }
+
+    fn set_progress(&self, id: usize, perc: f32) {
+        if let Some(item) = self.items.lock().unwrap().get_mut(id) {
+            if let DownloadState::Running(_) = item.state {
+                item.state = DownloadState::Running(perc);
+            }
+        }
+    }
+
+    /// Records the outcome of a download served by a worker
+    fn complete(&self, id: usize, result: Option<Result<(), String>>) {
+        // Downloads interrupted by quitting are served again at the next start
+        if self.quit.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let mut items = self.items.lock().unwrap();
+        let item = match items.get_mut(id) {
+            Some(item) => item,
+            None => return,
+        };
+        let name = &item.request.name;
+
+        let event = match result {
+            None => {
+                item.state = DownloadState::Cancelled;
+                let _ = std::fs::remove_dir_all(&item.dir);
+                None
+            }
+            Some(Ok(_)) => {
+                item.state = DownloadState::Done;
+                Some(UserEvent::DownloadFinished(item.song.clone()))
+            }
+            // The download goes back in the queue
+            Some(Err(msg)) if item.attempts < MAX_ATTEMPTS => {
+                let backoff = BASE_BACKOFF * 2u32.pow(item.attempts - 1);
+                item.state = DownloadState::Retrying(Instant::now() + backoff);
+                Some(UserEvent::DownloadRetrying(format!("{}: {}", name, msg)))
+            }
+            Some(Err(msg)) => {
+                item.state = DownloadState::Failed(msg.clone());
+                let _ = std::fs::remove_dir_all(&item.dir);
+                Some(UserEvent::DownloadError(format!("{}: {}", name, msg)))
+            }
+        };
+
+        if let Some(event) = event {
+            let _ = self.user_event.send(event);
+        }
+        self.available.notify_all();
+    }
+}
+
+/// Serves downloads on a pool of internal workers, so that they never
+/// block searches and several of them can run at the same time.
+///
+/// Every download goes through the states described by `DownloadState`.
+/// Failed downloads are retried automatically with an increasing delay,
+/// and can also be cancelled or retried on request. Outcomes of downloads
+/// are reported as `UserEvent`s, while `Downloads` gives a view of all the
+/// registered downloads, progress included.
+///
+/// Files are written to a partial folder inside the cache, and moved
+/// to their final path only once complete. This way a download
+/// interrupted by quitting can be found by `interrupted` at the next start.
+pub struct DownloadManager {
+    shared: Arc<Shared>,
+}
+
+impl DownloadManager {
+    /// Creates a manager serving up to `concurrency` downloads at the same time,
+    /// using the given `plugins`. Downloads are written inside `cache` until
+    /// they complete.
+    pub fn new(
+        user_event: Sender<UserEvent>,
+        concurrency: usize,
+        cache: &Path,
+        plugins: &[String],
+    ) -> Result<Self, String> {
+        let shared = Arc::new(Shared {
+            items: Mutex::new(vec![]),
+            partial: cache.join(PARTIAL),
+            available: Condvar::new(),
+            quit: AtomicBool::new(false),
+            user_event,
+        });
+
+        for slot in 0..concurrency.clamp(1, MAX_CONCURRENT_DOWNLOADS) {
+            // Without any plugin the manager still starts, as the local one
+            // needs none, and only the downloads requested fail
+            let mut manager = plugin_registry::plugin_manager(plugins);
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(msg) => return Err(format!("Error in runtime creation: {}", msg)),
+            };
+
+            let shared = shared.clone();
+            let _thread = std::thread::spawn(move || {
+                while let Some(job) = shared.next_job() {
+                    *PROGRESS[slot].lock().unwrap() = Some((job.id, shared.clone()));
+
+                    // Downloads can't be resumed, so leftovers of a previous
+                    // attempt are discarded
+                    let _ = clear_partial(&job.dir);
+                    // Songs are downloaded by the plugin they have been found with
+                    let selected = match manager.as_mut() {
+                        Ok(manager) if job.plugin.is_empty() => Ok(manager),
+                        Ok(manager) => match manager.set_default(&job.plugin) {
+                            Ok(_) => Ok(manager),
+                            Err(error) => Err(format!("plugin `{}`: {}", job.plugin, error)),
+                        },
+                        Err(msg) => Err(msg.clone()),
+                    };
+                    let result = match selected {
+                        Ok(manager) => runtime.block_on(async {
+                            tokio::select! {
+                                result = manager.download(&job.url, &job.path, follower(slot)) => {
+                                    Some(result.map_err(|error| error.to_string()))
+                                }
+                                _ = cancelled(&job.cancel) => None,
+                            }
+                        }),
+                        Err(msg) => Some(Err(msg)),
+                    };
+                    // The file is moved to its final location only once complete
+                    let result = match result {
+                        Some(Ok(_)) => Some(finalize(&job.dir, Path::new(&job.path), &job.target)),
+                        result => result,
+                    };
+
+                    *PROGRESS[slot].lock().unwrap() = None;
+                    shared.complete(job.id, result);
+                }
+            });
+        }
+
+        Ok(DownloadManager { shared })
+    }
+
+    /// Registers a new download, which will be served as soon as a worker
+    /// is free. `song` is the song the downloaded file is associated to.
+    /// Songs already being downloaded are ignored.
+    pub fn enqueue(&self, request: DownloadRequest, song: Song) {
+        let mut items = self.shared.items.lock().unwrap();
+        if items
+            .iter()
+            .any(|item| item.song.path() == song.path() && item.state.is_active())
+        {
+            return;
+        }
+
+        // The partial folder is named after the final file, so that
+        // an interrupted download can be found again
+        let stem = Path::new(song.path()).file_stem().unwrap_or_default();
+        let dir = self.shared.partial.join(stem);
+        if let Err(error) = write_manifest(&dir, &request, song.path()) {
+            let _ = self
+                .shared
+                .user_event
+                .send(UserEvent::DownloadError(format!(
+                    "{}: {}",
+                    request.name, error
+                )));
+            return;
+        }
+
+        items.push(DownloadItem {
+            request,
+            song,
+            dir,
+            state: DownloadState::Queued,
+            attempts: 0,
+            cancel: Arc::new(AtomicBool::new(false)),
+        });
+        self.shared.available.notify_one();
+    }
+
+    /// Cancels a download, if it's still queued or running
+    pub fn cancel(&self, id: usize) {
+        let mut items = self.shared.items.lock().unwrap();
+        if let Some(item) = items.get_mut(id) {
+            match item.state {
+                // The worker serving it will mark it as cancelled
+                DownloadState::Running(_) => item.cancel.store(true, Ordering::SeqCst),
+                DownloadState::Queued | DownloadState::Retrying(_) => {
+                    item.state = DownloadState::Cancelled;
+                    let _ = std::fs::remove_dir_all(&item.dir);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Puts a failed or cancelled download back in the queue
+    pub fn retry(&self, id: usize) {
+        let mut items = self.shared.items.lock().unwrap();
+        if let Some(item) = items.get_mut(id) {
+            if let DownloadState::Failed(_) | DownloadState::Cancelled = item.state {
+                if let Err(error) = write_manifest(&item.dir, &item.request, item.song.path()) {
+                    item.state = DownloadState::Failed(error.to_string());
+                    return;
+                }
+                item.state = DownloadState::Queued;
+                item.attempts = 0;
+                self.shared.available.notify_one();
+            }
+        }
+    }
+
+    /// Looks for downloads interrupted in a previous session. Their partial
+    /// files are discarded, and the requests needed to serve them again are
+    /// returned. Downloads that can't be served again are discarded entirely.
+    pub fn interrupted(&self) -> Vec<DownloadRequest> {
+        let entries = match std::fs::read_dir(&self.shared.partial) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+
+        let mut requests = vec![];
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            match read_manifest(&path) {
+                // The download had completed, but its folder was left behind
+                Some((_, target)) if target.exists() => {
+                    let _ = std::fs::remove_dir_all(&path);
+                }
+                Some((request, _)) if clear_partial(&path).is_ok() => requests.push(request),
+                _ if path.is_dir() => {
+                    let _ = std::fs::remove_dir_all(&path);
+                }
+                _ => {
+                    let _ = std::fs::remove_file(&path);
+                }
+            }
+        }
+        requests
+    }
+
+    /// Returns a handle to the list of registered downloads
+    pub fn downloads(&self) -> Downloads {
+        Downloads(self.shared.clone())
+    }
+}
+
+impl Drop for DownloadManager {
+    fn drop(&mut self) {
+        self.shared.quit.store(true, Ordering::SeqCst);
+        for item in self.shared.items.lock().unwrap().iter() {
+            item.cancel.store(true, Ordering::SeqCst);
+        }
+        self.shared.available.notify_all();
+    }
+}
+
+/// Read-only view of the downloads registered in a `DownloadManager`.
+/// It can be cloned and read without blocking on the workers.
+#[derive(Clone)]
+pub struct Downloads(Arc<Shared>);
+
+impl Downloads {
+    /// Returns the state of every download, in registration order
+    pub fn entries(&self) -> Vec<DownloadEntry> {
+        self.0
+            .items
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|item| DownloadEntry {
+                name: item.request.name.clone(),
+                state: item.state.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Resolves once `cancel` is set
+async fn cancelled(cancel: &AtomicBool) {
+    while !cancel.load(Ordering::SeqCst) {
+        tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+    }
+}
+
+/// Updates the download served by the worker at `SLOT` with
+/// the progress received through `rx`
+fn follow_progress<const SLOT: usize>(rx: Receiver<f32>) {
+    while let Ok(value) = rx.recv() {
+        if let Some((id, shared)) = PROGRESS[SLOT].lock().unwrap().as_ref() {
+            shared.set_progress(*id, value);
+        }
+        if value >= 100.0 {
+            break;
+        }
+    }
+}
+
+/// Returns the progress follower of the worker at `slot`
+fn follower(slot: usize) -> ProgressFollowerFn {
+    match slot {
+        0 => follow_progress::<0>,
+        1 => follow_progress::<1>,
+        2 => follow_progress::<2>,
+        _ => follow_progress::<3>,
+    }
+}
@@ -1,7 +1,11 @@
 use phosphorus_core::song::Song;
 use std::sync::mpsc::Receiver;
 
-use phosphorus_core::plugin_manager::query::{QueryResult, QueryResultData};
+use crate::config::RepeatMode;
+
+use super::{notification::Notification, plugin_registry::SearchResult};
+
+use phosphorus_core::plugin_manager::query::QueryResultData;
 use tuirealm::{listener::Poll, Event};
 
 #[derive(Clone, PartialOrd, Debug)]
@@ -14,34 +18,71 @@ pub enum UserEvent {
     SecondaryWindowClosed,
     /// Sent a query to the `plugin_manager`
     QuerySent,
-    /// A query has produced a successfull result
-    QueryResult(QueryResult),
+    /// A query has produced a successfull result. When several plugins
+    /// serve the query, these are the results of the first to answer
+    QueryResult(Vec<SearchResult>),
+    /// More results of the query being served, to append to the previous ones
+    QueryResultExtended(Vec<SearchResult>),
+    /// Every plugin serving the query has answered
+    QueryCompleted,
+    /// The page of results requested brought nothing new
+    NoMoreResults,
     /// A query has failed and produced and error
     QueryError(String),
+    /// A query has been abandoned after the given number of seconds
+    QueryTimedOut(u64),
+    /// A query has been abandoned on request
+    QueryCancelled,
     /// Started playing a song
     PlaySong(Song),
+    /// A song couldn't be played
+    PlayError(String),
+    /// The current song has been paused
+    PlaybackPaused,
+    /// The current song has been resumed after a pause
+    PlaybackResumed,
+    /// The current song has been stopped
+    PlaybackStopped,
+    /// The current song has reached its end
+    TrackEnded,
+    /// The given songs, taken from the playlist at index `playlist`,
+    /// have to be queued in place of the current ones
+    QueueRequested { playlist: usize, songs: Vec<Song> },
+    /// The next song in the queue has been requested
+    NextRequested,
+    /// The previously reproduced song has been requested
+    PreviousRequested,
+    /// The volume level, as a percentage, or the mute state has changed
+    VolumeChanged { level: u8, muted: bool },
+    /// The shuffle or the repeat mode has changed
+    QueueModesChanged { shuffle: bool, repeat: RepeatMode },
     /// Started downloadin a song
     DownloadRequested(QueryResultData),
-    /// Registers a new download to serve
-    DownloadRegistered(String),
     /// A download has succesfully finished
     DownloadFinished(Song),
-    /// A download has failed
+    /// A download has failed, and won't be attempted again
     DownloadError(String),
+    /// A download has failed, and will be attempted again
+    DownloadRetrying(String),
+    /// The downloads window has been opened
+    DownloadsViewOpened,
+    /// A notification has been raised
+    Notified(Notification),
+    /// The notifications window has been opened
+    NotificationsViewOpened,
+    /// The plugin with the given name now serves the queries
+    DefaultPluginChanged(String),
+    /// A plugin couldn't be registered or used
+    PluginError(String),
+    /// The plugins window has been opened
+    PluginsViewOpened,
 }
 
+/// Events are compared by variant, ignoring what they carry, so that
+/// subscribing to a variant receives every event of that kind
 impl PartialEq for UserEvent {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::QueryResult(_), _other) => true,
-            (Self::QueryError(_), _other) => true,
-            (Self::PlaySong(_), _other) => true,
-            (Self::DownloadRequested(_), _other) => true,
-            (Self::DownloadRegistered(_), _other) => true,
-            (Self::DownloadFinished(_), _other) => true,
-            (Self::DownloadError(_), _other) => true,
-            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
-        }
+        std::mem::discriminant(self) == std::mem::discriminant(other)
     }
 }
 
@@ -0,0 +1,174 @@
+use std::{
+    cmp::Reverse,
+    sync::{Arc, Mutex},
+};
+
+use phosphorus_core::song::Song;
+
+/// Name of the plugin searching the songs already downloaded
+pub const LOCAL_PLUGIN: &str = "Local";
+
+/// Splits `text` into lowercase alphanumeric words
+fn words(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Number of single character edits needed to turn `a` into `b`
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_ch) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_ch) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_ch != *b_ch);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Tells how well `token` matches one of `words`, if at all. Exact
+/// matches are preferred over prefixes, which are preferred over
+/// words containing a typo.
+fn token_score(token: &str, words: &[String]) -> Option<u32> {
+    // Longer tokens tolerate more typos
+    let tolerance = token.chars().count() / 4;
+    words
+        .iter()
+        .filter_map(|word| {
+            if word == token {
+                Some(3)
+            } else if word.starts_with(token) {
+                Some(2)
+            } else if tolerance > 0 && distance(word, token) <= tolerance {
+                Some(1)
+            } else {
+                None
+            }
+        })
+        .max()
+}
+
+/// Tells how well `text` matches the query `tokens`. Every token
+/// has to match for the text to be found.
+fn text_score(tokens: &[String], text: &str) -> Option<u32> {
+    let words = words(text);
+    tokens.iter().map(|token| token_score(token, &words)).sum()
+}
+
+/// Tells how well `song` matches the query `tokens` by name, artist
+/// and album
+fn score(tokens: &[String], song: &Song) -> Option<u32> {
+    let details = song.details();
+    let mut text = details.name().to_string();
+    for field in [details.artist(), details.album()].into_iter().flatten() {
+        text.push(' ');
+        text.push_str(field);
+    }
+    text_score(tokens, &text)
+}
+
+/// Songs already downloaded, searched by the local plugin.
+/// It can be cloned and shared with the workers serving queries.
+#[derive(Clone, Default)]
+pub struct Library(Arc<Mutex<Vec<Song>>>);
+
+impl Library {
+    pub fn new(songs: Vec<Song>) -> Self {
+        Library(Arc::new(Mutex::new(songs)))
+    }
+
+    /// Adds a newly downloaded song, unless it's already in the library
+    pub fn add(&self, song: Song) {
+        let mut songs = self.0.lock().unwrap();
+        if songs.iter().all(|known| known.path() != song.path()) {
+            songs.push(song);
+        }
+    }
+
+    /// Returns the first song name or artist starting with `prefix`,
+    /// ignoring case
+    pub fn complete(&self, prefix: &str) -> Option<String> {
+        let prefix = prefix.to_lowercase();
+        let songs = self.0.lock().unwrap();
+        songs
+            .iter()
+            .flat_map(|song| {
+                let details = song.details();
+                [Some(details.name()), details.artist()]
+            })
+            .flatten()
+            .find(|candidate| {
+                let candidate = candidate.to_lowercase();
+                candidate.len() > prefix.len() && candidate.starts_with(&prefix)
+            })
+            .map(str::to_string)
+    }
+
+    /// Returns the songs fuzzily matching `query`, best matches first
+    pub fn search(&self, query: &str) -> Vec<Song> {
+        let tokens = words(query);
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        let songs = self.0.lock().unwrap();
+        let mut found: Vec<(u32, &Song)> = songs
+            .iter()
+            .filter_map(|song| score(&tokens, song).map(|score| (score, song)))
+            .collect();
+        found.sort_by_key(|(score, _)| Reverse(*score));
+        found.into_iter().map(|(_, song)| song.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_single_character_edits() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("punk", "punk"), 0);
+        assert_eq!(distance("punk", ""), 4);
+        assert_eq!(distance("punk", "pank"), 1);
+        assert_eq!(distance("punk", "puk"), 1);
+        assert_eq!(distance("punk", "punks"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        // Characters are compared, not bytes
+        assert_eq!(distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn splits_text_into_lowercase_words() {
+        assert_eq!(
+            words("Daft Punk - Around the World (Radio Edit)"),
+            vec!["daft", "punk", "around", "the", "world", "radio", "edit"]
+        );
+        assert!(words(" - ").is_empty());
+    }
+
+    #[test]
+    fn prefers_exact_matches_to_prefixes_and_typos() {
+        let words = words("Daft Punk Around the World");
+        assert_eq!(token_score("world", &words), Some(3));
+        assert_eq!(token_score("wor", &words), Some(2));
+        assert_eq!(token_score("wrold", &words), None);
+        assert_eq!(token_score("aroumd", &words), Some(1));
+        // Short tokens must be exact or prefixes
+        assert_eq!(token_score("thw", &words), None);
+    }
+
+    #[test]
+    fn requires_every_token_to_match() {
+        let text = "Around the World Daft Punk";
+        assert_eq!(text_score(&words("daft world"), text), Some(6));
+        assert_eq!(text_score(&words("daft wor"), text), Some(5));
+        assert_eq!(text_score(&words("daft queen"), text), None);
+        assert!(text_score(&words("around"), text) > text_score(&words("aroumd"), text));
+    }
+}
@@ -1,5 +1,5 @@
 use phosphorus_core::{
-    playlist_manager::PlaylistManager,
+    playlist_manager::{PlaylistManager, ALL_SONGS},
     queue::QueueManager,
     song::{Song, SongDetails},
 };
@@ -8,7 +8,6 @@ use std::{
     time::Duration,
 };
 
-use phosphorus_core::plugin_manager::query::{QueryInfo, QueryResult, QueryResultData};
 use tuirealm::{
     event::{Key, KeyEvent, KeyModifiers},
     terminal::TerminalBridge,
@@ -17,27 +16,50 @@ use tuirealm::{
 };
 
 use crate::{
-    config::Paths,
-    player::Player,
+    config::{Paths, RepeatMode, Settings},
+    player::{Player, Position, State},
     ui::{
-        app_window::AppWindow, event::UserEventPort, player_bar::PlayerBar, status_bar::StatusBar,
-        top_bar::TopBar,
+        app_window::AppWindow, download_manager::Downloads, event::UserEventPort,
+        player_bar::PlayerBar, status_bar::StatusBar, top_bar::TopBar,
     },
 };
 
-use self::{event::UserEvent, querier::Querier};
+use self::{
+    download_manager::{DownloadManager, DownloadRequest},
+    event::UserEvent,
+    local_library::Library,
+    notification::{Notifications, Notifier, Severity},
+    plugin_registry::{self, ResultData, SearchResult},
+    querier::Querier,
+    search_cache::SearchCache,
+    search_history::SearchHistory,
+};
 
 mod app_window;
-mod event;
+mod download_manager;
+pub mod event;
+mod local_library;
+mod notification;
 mod player_bar;
 mod playlist_list;
+mod plugin_registry;
 mod querier;
 mod queue;
+mod search_cache;
+mod search_history;
+mod search_query;
 mod secondary_window;
 mod status_bar;
 mod top_bar;
 mod welcome_window;
 
+/// Amount, as a percentage, by which the volume is changed
+const VOLUME_STEP: u8 = 5;
+
+/// Once a song has been playing for longer than this, `Previous`
+/// restarts it instead of going to the previous one
+const RESTART_THRESHOLD: Duration = Duration::from_secs(3);
+
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum Id {
     Label,
@@ -66,17 +88,87 @@ pub enum AppMsg {
     ShowPlaylist,
     /// Boh
     QuerySent(String),
+    /// Sends the query again, ignoring the cached results
+    RefreshQuery(String),
+    /// Abandons the search being served
+    CancelQuery,
+    /// Requests the next page of results of the last search
+    LoadMoreResults,
+    /// Forgets every query sent so far
+    ClearSearchHistory,
     /// Plays&downloads a song retrieving it from query results
-    PlayFromResult(QueryResultData),
-    /// Plays a song from a playlist
-    PlayFromPlaylist(usize),
+    PlayFromResult(SearchResult),
+    /// Plays the song at `index` among the songs of the playlist at index
+    /// `playlist`, queueing the ones that follow
+    PlayFromPlaylist {
+        playlist: usize,
+        songs: Vec<Song>,
+        index: usize,
+    },
     /// Plays the song
     Play(Song),
+    /// Pauses the current song if it's playing, resumes it if it's paused
     PlayPause,
+    /// Stops the current song
+    Stop,
+    /// The player has moved to a new state
+    PlaybackChanged(State),
+    /// Moves the current song to the given position
+    Seek(Duration),
+    /// Moves the current song forward by the given amount
+    SeekForward(Duration),
+    /// Moves the current song backward by the given amount
+    SeekBackward(Duration),
+    /// Moves the current song to the given fraction of its duration
+    SeekTo(f64),
+    /// Plays the next song in the queue
+    Next,
+    /// Plays the previous song, or restarts the current one if
+    /// it has been playing for a while
+    Previous,
+    /// Turns shuffle on or off
+    ToggleShuffle,
+    /// Moves to the next repeat mode
+    CycleRepeat,
+    /// Raises the volume by one step
+    VolumeUp,
+    /// Lowers the volume by one step
+    VolumeDown,
+    /// Mutes the player if it isn't muted, restores the volume otherwise
+    ToggleMute,
     /// Tried to use a missing song. Missing means that the song isn't
     /// in a playlist, or the queue or in the result window.
     MissingSong,
-    DownloadSong(QueryResultData),
+    /// Downloads a song retrieving it from query results
+    DownloadSong(SearchResult),
+    /// A song has been completely downloaded. The reason why it couldn't
+    /// be added to the library is given, if that's the case.
+    DownloadCompleted(Song, Option<String>),
+    /// The downloads window has been requested
+    ShowDownloads,
+    /// The notifications window has been requested
+    ShowNotifications,
+    /// The plugins window has been requested
+    ShowPlugins,
+    /// Makes the plugin with the given name the one serving queries
+    SetDefaultPlugin(String),
+    /// Switches between searching with all the plugins and with the default one
+    ToggleFederatedSearch,
+    /// Raises a notification with the given severity and message
+    Notify(Severity, String),
+    /// A search has failed for the given reason
+    SearchFailed(String),
+    /// A song couldn't be played for the given reason
+    PlaybackFailed(String),
+    /// A download has failed for the given reason. If the flag is set,
+    /// it will be attempted again.
+    DownloadFailed(String, bool),
+    /// A plugin couldn't be registered or used for the given reason
+    PluginFailed(String),
+    /// Cancels the download with the given id
+    CancelDownload(usize),
+    /// Attempts again the failed or cancelled download with the given id
+    RetryDownload(usize),
     None,
 }
 
@@ -127,16 +219,28 @@ pub struct Model {
     active: FocusableItem,
     is_secondary_window_active: bool,
     paths: Paths,
+    /// User preferences persisted across sessions
+    settings: Settings,
     user_event: Sender<UserEvent>,
+    /// Used to let the user know about errors and other facts
+    notifier: Notifier,
     /// Used to send queries to plugin manager
     querier: Querier,
+    /// Songs already downloaded, searched by the local plugin
+    library: Library,
+    /// Queries sent so far, shared with the search bar
+    history: SearchHistory,
+    /// Used to download songs
+    downloader: DownloadManager,
     /// Used to reproduce audio files
     player: Player,
-    /// Used to track reproduction state:
-    /// None: no song is being played
-    /// Some(true): a song is current being played
-    /// Some(false): a song is being played, but has been paused
-    playing: Option<bool>,
+    /// Used to track reproduction state, as reported by the player
+    playing: State,
+    /// Path of the song to play as soon as its download completes
+    play_after_download: Option<String>,
+    /// Tells whether the player has been muted. Muting doesn't
+    /// change the volume level stored in `settings`
+    muted: bool,
 }
 
 impl Model {
@@ -144,23 +248,82 @@ impl Model {
         paths: Paths,
         playlist_manager: PlaylistManager,
         queue_manager: QueueManager,
-    ) -> Result<Self, ()> {
+    ) -> Result<Self, String> {
         let (tx, rx) = std::sync::mpsc::channel();
-        let querier = Querier::new(tx.clone())?;
-
-        Ok(Self {
-            app: Self::init_app(playlist_manager, queue_manager, rx),
+        let settings = Settings::load(&paths);
+        // Plugins in shared libraries must be loaded before being registered
+        let load_errors = plugin_registry::load(&paths.plugins());
+
+        // Songs already downloaded are searched by the local plugin
+        let library = Library::new(
+            playlist_manager
+                .playlists()
+                .iter()
+                .find(|playlist| playlist.name() == ALL_SONGS)
+                .map(|playlist| playlist.songs().to_vec())
+                .unwrap_or_default(),
+        );
+        let history = SearchHistory::load(paths.search_history());
+        let querier = Querier::new(
+            tx.clone(),
+            settings.search_timeout(),
+            settings.plugins(),
+            settings.default_plugin(),
+            library.clone(),
+            SearchCache::new(paths.cache()),
+        );
+        let player = Player::try_new(tx.clone()).expect("Cannot initialize the player process");
+        let notifier = Notifier::new(tx.clone());
+        for msg in load_errors {
+            notifier.notify(Severity::Warning, msg);
+        }
+        let downloader = DownloadManager::new(
+            tx.clone(),
+            settings.concurrent_downloads(),
+            paths.cache(),
+            querier.plugins(),
+        )?;
+
+        let mut model = Self {
+            app: Self::init_app(
+                playlist_manager,
+                queue_manager,
+                player.position(),
+                downloader.downloads(),
+                notifier.history(),
+                querier.plugins().to_vec(),
+                history.clone(),
+                library.clone(),
+                rx,
+            ),
             quit: false,
             redraw: true,
             terminal: TerminalBridge::new().expect("Cannot initialize terminal"),
             active: FocusableItem::SearchBar,
             is_secondary_window_active: false,
             paths,
+            settings,
             user_event: tx,
+            notifier,
             querier,
-            player: Player::try_new().expect("Cannot initialize the player process"),
-            playing: None,
-        })
+            library,
+            history,
+            downloader,
+            player,
+            playing: State::None,
+            play_after_download: None,
+            muted: false,
+        };
+
+        // Restores the volume and the queue modes of the previous session
+        model.apply_volume();
+        model.notify_queue_modes();
+
+        // Downloads interrupted in the previous session are served again
+        for request in model.downloader.interrupted() {
+            model.download(request);
+        }
+        Ok(model)
     }
 
     pub fn view(&mut self) {
@@ -192,6 +355,12 @@ impl Model {
     pub fn init_app(
         playlist_manager: PlaylistManager,
         queue_manager: QueueManager,
+        position: Position,
+        downloads: Downloads,
+        notifications: Notifications,
+        plugins: Vec<String>,
+        history: SearchHistory,
+        library: Library,
         rx: Receiver<UserEvent>,
     ) -> Application<Id, AppMsg, UserEvent> {
         // Setup application
@@ -208,17 +377,31 @@ impl Model {
 
         // Mounts the components
         assert!(app
-            .mount(Id::TopBar, TopBar::default().boxed(), Vec::default())
+            .mount(
+                Id::TopBar,
+                TopBar::new(downloads, history, library).boxed(),
+                Vec::default()
+            )
             .is_ok());
         assert!(app
             .mount(
                 Id::AppWindow,
-                Box::new(AppWindow::new(playlist_manager, queue_manager)),
+                Box::new(AppWindow::new(
+                    playlist_manager,
+                    queue_manager,
+                    downloads.clone(),
+                    notifications,
+                    plugins
+                )),
                 Vec::default()
             )
             .is_ok());
         assert!(app
-            .mount(Id::PlayerBar, PlayerBar::default().boxed(), Vec::default())
+            .mount(
+                Id::PlayerBar,
+                PlayerBar::new(position).boxed(),
+                Vec::default()
+            )
             .is_ok());
         assert!(app
             .mount(Id::StatusBar, StatusBar::new().boxed(), Vec::default())
@@ -241,7 +424,10 @@ impl Model {
             .subscribe(
                 &Id::AppWindow,
                 Sub::new(
-                    SubEventClause::User(UserEvent::SecondaryWindowClosed),
+                    SubEventClause::Keyboard(KeyEvent {
+                        code: Key::Char('d'),
+                        modifiers: KeyModifiers::CONTROL
+                    }),
                     tuirealm::SubClause::Always
                 )
             )
@@ -251,7 +437,10 @@ impl Model {
             .subscribe(
                 &Id::AppWindow,
                 Sub::new(
-                    SubEventClause::User(UserEvent::QueryResult(QueryResult::default())),
+                    SubEventClause::Keyboard(KeyEvent {
+                        code: Key::Char('n'),
+                        modifiers: KeyModifiers::CONTROL
+                    }),
                     tuirealm::SubClause::Always
                 )
             )
@@ -259,9 +448,12 @@ impl Model {
 
         assert!(app
             .subscribe(
-                &Id::PlayerBar,
+                &Id::AppWindow,
                 Sub::new(
-                    SubEventClause::User(UserEvent::PlaySong(Song::default())),
+                    SubEventClause::Keyboard(KeyEvent {
+                        code: Key::Char('p'),
+                        modifiers: KeyModifiers::CONTROL
+                    }),
                     tuirealm::SubClause::Always
                 )
             )
@@ -269,11 +461,64 @@ impl Model {
 
         assert!(app
             .subscribe(
-                &Id::PlayerBar,
+                &Id::AppWindow,
+                Sub::new(
+                    SubEventClause::Keyboard(KeyEvent {
+                        code: Key::Char('f'),
+                        modifiers: KeyModifiers::CONTROL
+                    }),
+                    tuirealm::SubClause::Always
+                )
+            )
+            .is_ok());
+
+        // Ticks are used to refresh the downloads and notifications windows
+        assert!(app
+            .subscribe(
+                &Id::AppWindow,
                 Sub::new(SubEventClause::Tick, tuirealm::SubClause::Always)
             )
             .is_ok());
 
+        // User events are compared by variant, so one subscription
+        // per variant handled by the app window is enough
+        for event in [
+            UserEvent::SecondaryWindowClosed,
+            UserEvent::QueryResult(vec![]),
+            UserEvent::QueryResultExtended(vec![]),
+            UserEvent::NoMoreResults,
+            UserEvent::PlaySong(Song::default()),
+            UserEvent::TrackEnded,
+            UserEvent::NextRequested,
+            UserEvent::PreviousRequested,
+            UserEvent::QueueRequested {
+                playlist: 0,
+                songs: vec![],
+            },
+            UserEvent::QueueModesChanged {
+                shuffle: false,
+                repeat: RepeatMode::Off,
+            },
+            UserEvent::DownloadFinished(Song::default()),
+            UserEvent::DefaultPluginChanged(String::new()),
+        ] {
+            assert!(app
+                .subscribe(
+                    &Id::AppWindow,
+                    Sub::new(SubEventClause::User(event), tuirealm::SubClause::Always)
+                )
+                .is_ok());
+        }
+
+        // The player bar needs ticks and player events to follow the
+        // reproduction, and mouse events to seek
+        assert!(app
+            .subscribe(
+                &Id::PlayerBar,
+                Sub::new(SubEventClause::Any, tuirealm::SubClause::Always)
+            )
+            .is_ok());
+
         assert!(app
             .subscribe(
                 &Id::StatusBar,
@@ -281,13 +526,11 @@ impl Model {
             )
             .is_ok());
 
+        // The download tracker reads the state of downloads on ticks
         assert!(app
             .subscribe(
                 &Id::TopBar,
-                Sub::new(
-                    SubEventClause::User(UserEvent::DownloadRegistered(String::default())),
-                    tuirealm::SubClause::Always
-                )
+                Sub::new(SubEventClause::Tick, tuirealm::SubClause::Always)
             )
             .is_ok());
 
@@ -297,11 +540,98 @@ impl Model {
         app
     }
 
-    /// A utility function which uses `QueryResultData` to create a `Song`
+    /// Lets the user know that a command couldn't reach the player
+    fn check_player<E>(&self, result: Result<(), E>) {
+        if result.is_err() {
+            self.notifier.notify(
+                Severity::Error,
+                String::from("The player is not responding"),
+            );
+        }
+    }
+
+    /// Persists settings, letting the user know if it fails
+    fn save_settings(&self) {
+        if let Err(error) = self.settings.save() {
+            self.notifier.notify(
+                Severity::Warning,
+                format!("Settings couldn't be saved: {}", error),
+            );
+        }
+    }
+
+    /// Moves the focus to the secondary window just opened by the app
+    /// window, announcing it with `event`. The app window has already
+    /// focused the new window among its children, so activating it once
+    /// is enough.
+    fn focus_secondary_window(&mut self, event: UserEvent) {
+        let _ = self.user_event.send(event);
+        self.active = FocusableItem::SecondaryWindow;
+        self.is_secondary_window_active = true;
+        assert!(self.app.active(&self.active.to_id()).is_ok());
+    }
+
+    /// Adds `query` to the search history, letting the user know if it
+    /// can't be persisted
+    fn remember(&self, query: &str) {
+        if let Err(error) = self.history.push(query) {
+            self.notifier.notify(
+                Severity::Warning,
+                format!("Search history couldn't be saved: {}", error),
+            );
+        }
+    }
+
+    /// Changes and persists the volume level, unmuting the player
+    fn set_volume(&mut self, level: u8) {
+        self.settings.set_volume(level);
+        self.save_settings();
+        self.muted = false;
+        self.apply_volume();
+    }
+
+    /// Sends the current volume to the player and lets the
+    /// interface know about it
+    fn apply_volume(&mut self) {
+        let level = self.settings.volume();
+        let volume = if self.muted {
+            0.0
+        } else {
+            level as f32 / 100.0
+        };
+        self.check_player(self.player.set_volume(volume));
+        let _ = self.user_event.send(UserEvent::VolumeChanged {
+            level,
+            muted: self.muted,
+        });
+    }
+
+    /// Lets the interface know about the current queue modes
+    fn notify_queue_modes(&self) {
+        let _ = self.user_event.send(UserEvent::QueueModesChanged {
+            shuffle: self.settings.shuffle(),
+            repeat: self.settings.repeat(),
+        });
+    }
+
+    /// Downloads the song described by `request`, unless it has already
+    /// been downloaded, and returns the associated `Song`.
+    fn download(&self, request: DownloadRequest) -> Song {
+        let song = self.create_song_file(&request);
+        if std::path::Path::new(song.path()).exists() {
+            return song;
+        }
+
+        self.downloader.enqueue(request, song.clone());
+        song
+    }
+
+    /// A utility function which uses `DownloadRequest` to create a `Song`
     /// instance associated to the file `file_name.mp3` in the `Paths.download`
     /// folder. The relative meta-file has the same name as the 'raw' one, but
     /// is a json file withing the `Paths.data` directory.
-    fn create_song_file(&self, query_data: &QueryResultData, file_name: &str) -> Song {
+    fn create_song_file(&self, request: &DownloadRequest) -> Song {
+        let file_name = request.file_name();
         let mp3 = self.paths.download().join(
             format!("{}.mp3", file_name)
                 .to_lowercase()
@@ -317,10 +647,10 @@ impl Model {
             mp3.to_str().unwrap(),
             json.to_str().unwrap(),
             SongDetails::new(
-                query_data.track_name(),
-                Some(query_data.artist_name()),
+                &request.name,
+                Some(request.artist.as_str()),
                 None,
-                Some(query_data.duration().clone()),
+                Some(request.duration),
             ),
         );
         song
@@ -369,66 +699,194 @@ impl Update<AppMsg> for Model {
                     }
                     assert!(self.app.active(&self.active.to_id()).is_ok());
                 }
-                AppMsg::ShowHelp => {
-                    let _ = self.user_event.send(UserEvent::HelpOpened);
-                    self.active = FocusableItem::SecondaryWindow;
-                    self.is_secondary_window_active = true;
-
-                    // Again, I don't know why this has to repeted
-                    assert!(self.app.active(&self.active.to_id()).is_ok());
-                    assert!(self.app.active(&self.active.to_id()).is_ok());
+                AppMsg::ShowHelp => self.focus_secondary_window(UserEvent::HelpOpened),
+                AppMsg::ShowDownloads => {
+                    self.focus_secondary_window(UserEvent::DownloadsViewOpened)
                 }
-                AppMsg::ShowPlaylist => {
-                    let _ = self.user_event.send(UserEvent::PlaylistViewOpened);
-                    self.active = FocusableItem::SecondaryWindow;
-                    self.is_secondary_window_active = true;
-
-                    // Again, I don't know why this has to repeted
-                    assert!(self.app.active(&self.active.to_id()).is_ok());
-                    assert!(self.app.active(&self.active.to_id()).is_ok());
+                AppMsg::ShowNotifications => {
+                    self.focus_secondary_window(UserEvent::NotificationsViewOpened)
+                }
+                AppMsg::ShowPlugins => self.focus_secondary_window(UserEvent::PluginsViewOpened),
+                AppMsg::SetDefaultPlugin(name) => {
+                    // The querier confirms the change with a `UserEvent`
+                    self.querier.set_default(name.clone());
+                    self.settings.set_default_plugin(name);
+                    self.save_settings();
+                }
+                AppMsg::ToggleFederatedSearch => {
+                    let federated = !self.settings.federated_search();
+                    self.settings.set_federated_search(federated);
+                    self.save_settings();
+                    let message = if federated {
+                        String::from("Searching with all plugins")
+                    } else {
+                        format!("Searching with {}", self.querier.default_plugin())
+                    };
+                    self.notifier.notify(Severity::Info, message);
                 }
+                AppMsg::Notify(severity, message) => self.notifier.notify(severity, message),
+                AppMsg::SearchFailed(error) => self
+                    .notifier
+                    .notify(Severity::Error, format!("Search failed: {}", error)),
+                AppMsg::PlaybackFailed(error) => self.notifier.notify(Severity::Error, error),
+                AppMsg::DownloadFailed(error, false) => self
+                    .notifier
+                    .notify(Severity::Error, format!("Download failed: {}", error)),
+                AppMsg::DownloadFailed(error, true) => self.notifier.notify(
+                    Severity::Warning,
+                    format!("Download failed, retrying: {}", error),
+                ),
+                AppMsg::PluginFailed(error) => self.notifier.notify(Severity::Error, error),
+                AppMsg::ShowPlaylist => self.focus_secondary_window(UserEvent::PlaylistViewOpened),
                 AppMsg::QuerySent(query) => {
-                    let query = QueryInfo::as_raw(&query);
-                    self.querier.query(query);
+                    self.remember(&query);
+                    self.querier
+                        .query(&query, self.settings.federated_search(), false);
                     let _ = self.user_event.send(UserEvent::QuerySent);
                 }
+                AppMsg::RefreshQuery(query) => {
+                    self.remember(&query);
+                    self.querier
+                        .query(&query, self.settings.federated_search(), true);
+                    let _ = self.user_event.send(UserEvent::QuerySent);
+                }
+                AppMsg::ClearSearchHistory => match self.history.clear() {
+                    Ok(_) => self
+                        .notifier
+                        .notify(Severity::Info, String::from("Search history cleared")),
+                    Err(error) => self.notifier.notify(
+                        Severity::Warning,
+                        format!("Search history couldn't be cleared: {}", error),
+                    ),
+                },
+                AppMsg::LoadMoreResults => {
+                    if self.querier.more() {
+                        let _ = self.user_event.send(UserEvent::QuerySent);
+                    }
+                }
+                AppMsg::CancelQuery => {
+                    self.querier.cancel();
+                    let _ = self.user_event.send(UserEvent::QueryCancelled);
+                }
                 AppMsg::Play(song) => {
-                    self.player.initiate(&song).expect("Error in reproduction!");
-                    let _ = self.user_event.send(UserEvent::PlaySong(song));
-                    self.playing = Some(true);
+                    // The engine will report the outcome with a `UserEvent`
+                    self.check_player(self.player.initiate(&song));
+                }
+                AppMsg::PlayPause => {
+                    let result = match self.playing {
+                        State::Playing => self.player.pause(),
+                        State::Paused => self.player.play(),
+                        State::None => Ok(()),
+                    };
+                    self.check_player(result);
+                }
+                AppMsg::Stop => {
+                    if self.playing != State::None {
+                        self.check_player(self.player.stop());
+                    }
+                }
+                AppMsg::PlaybackChanged(state) => self.playing = state,
+                AppMsg::Seek(position) => {
+                    self.check_player(self.player.seek(position));
+                }
+                AppMsg::SeekForward(step) => {
+                    self.check_player(self.player.seek_forward(step));
+                }
+                AppMsg::SeekBackward(step) => {
+                    self.check_player(self.player.seek_backward(step));
+                }
+                AppMsg::SeekTo(ratio) => {
+                    self.check_player(self.player.seek_to(ratio));
+                }
+                AppMsg::Next => {
+                    let _ = self.user_event.send(UserEvent::NextRequested);
+                }
+                AppMsg::Previous => {
+                    if self.playing != State::None
+                        && self.player.position().get() > RESTART_THRESHOLD
+                    {
+                        self.check_player(self.player.seek(Duration::ZERO));
+                    } else {
+                        let _ = self.user_event.send(UserEvent::PreviousRequested);
+                    }
+                }
+                AppMsg::ToggleShuffle => {
+                    self.settings.set_shuffle(!self.settings.shuffle());
+                    self.save_settings();
+                    self.notify_queue_modes();
+                }
+                AppMsg::CycleRepeat => {
+                    self.settings.set_repeat(self.settings.repeat().next());
+                    self.save_settings();
+                    self.notify_queue_modes();
+                }
+                AppMsg::VolumeUp => {
+                    let level = self.settings.volume().saturating_add(VOLUME_STEP);
+                    self.set_volume(level);
+                }
+                AppMsg::VolumeDown => {
+                    let level = self.settings.volume().saturating_sub(VOLUME_STEP);
+                    self.set_volume(level);
+                }
+                AppMsg::ToggleMute => {
+                    self.muted = !self.muted;
+                    self.apply_volume();
+                }
+                AppMsg::PlayFromPlaylist {
+                    playlist,
+                    songs,
+                    index,
+                } => {
+                    let song = match songs.get(index) {
+                        Some(song) => song.clone(),
+                        None => return Some(AppMsg::MissingSong),
+                    };
+                    let _ = self.user_event.send(UserEvent::QueueRequested {
+                        playlist,
+                        songs: songs[index + 1..].to_vec(),
+                    });
+                    return Some(AppMsg::Play(song));
                 }
-                AppMsg::DownloadSong(query_data) => {
-                    // Let download tracker know about the new download
-                    let _ = self.user_event.send(UserEvent::DownloadRegistered(
-                        query_data.track_name().to_string(),
-                    ));
-                    let file_name = phosphorus_core::file_name_from_basics(
-                        query_data.track_name(),
-                        query_data.artist_name(),
-                    );
-                    let song = self.create_song_file(&query_data, &file_name);
-
-                    let raw_path = self.paths.download().join(&file_name);
-                    self.querier.download(
-                        query_data.track_url().to_string(),
-                        raw_path.to_str().unwrap().to_string(),
-                        |rx| {
-                            loop {
-                                match rx.recv() {
-                                    Ok(value) => {
-                                        if value == 100.0 {
-                                            break;
-                                        }
-                                    }
-                                    Err(_) => {
-                                        // The sender has terminated sending data
-                                        break;
-                                    }
-                                }
-                            }
-                        },
-                    );
-                    let _ = self.user_event.send(UserEvent::DownloadFinished(song));
+                AppMsg::PlayFromResult(result) => {
+                    let track = match result.data {
+                        // Songs in the library are already downloaded
+                        ResultData::Local(song) => {
+                            self.play_after_download = None;
+                            return Some(AppMsg::Play(song));
+                        }
+                        ResultData::Remote(track) => track,
+                    };
+                    let song = self.download(DownloadRequest::new(&result.plugin, &track));
+                    if std::path::Path::new(song.path()).exists() {
+                        // The song has already been downloaded
+                        self.play_after_download = None;
+                        return Some(AppMsg::Play(song));
+                    }
+                    self.play_after_download = Some(song.path().to_string());
+                }
+                AppMsg::DownloadSong(result) => {
+                    // Songs in the library are already downloaded
+                    if let ResultData::Remote(track) = &result.data {
+                        self.download(DownloadRequest::new(&result.plugin, track));
+                    }
+                }
+                AppMsg::CancelDownload(id) => self.downloader.cancel(id),
+                AppMsg::RetryDownload(id) => self.downloader.retry(id),
+                AppMsg::DownloadCompleted(song, error) => {
+                    if let Some(error) = error {
+                        self.notifier.notify(
+                            Severity::Warning,
+                            format!(
+                                "Downloaded song couldn't be added to the library: {}",
+                                error
+                            ),
+                        );
+                    }
+                    self.library.add(song.clone());
+                    if self.play_after_download.as_deref() == Some(song.path()) {
+                        self.play_after_download = None;
+                        return Some(AppMsg::Play(song));
+                    }
                 }
                 _ => (),
             }
@@ -0,0 +1,115 @@
+use std::{
+    collections::VecDeque,
+    sync::{mpsc::Sender, Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tuirealm::props::Color;
+
+use super::event::UserEvent;
+
+/// Number of notifications remembered by the history
+const HISTORY_CAPACITY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// How long a notification stays on screen
+    pub fn lifetime(&self) -> Duration {
+        match self {
+            Severity::Info => Duration::from_secs(3),
+            Severity::Warning => Duration::from_secs(5),
+            Severity::Error => Duration::from_secs(8),
+        }
+    }
+
+    /// Color used to highlight a notification
+    pub fn color(&self) -> Color {
+        match self {
+            Severity::Info => Color::LightBlue,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::LightRed,
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "Info"),
+            Severity::Warning => write!(f, "Warning"),
+            Severity::Error => write!(f, "Error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+    /// When the notification has been raised
+    pub time: Instant,
+}
+
+impl Notification {
+    /// Tells whether the notification has been on screen for long enough
+    pub fn is_expired(&self) -> bool {
+        self.time.elapsed() >= self.severity.lifetime()
+    }
+}
+
+/// History of the notifications raised so far, oldest first.
+/// It can be cloned and shared with the interface.
+#[derive(Clone, Default)]
+pub struct Notifications(Arc<Mutex<VecDeque<Notification>>>);
+
+impl Notifications {
+    fn push(&self, notification: Notification) {
+        let mut history = self.0.lock().unwrap();
+        if history.len() == HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(notification);
+    }
+
+    /// Returns the notifications in the history, newest first
+    pub fn entries(&self) -> Vec<Notification> {
+        self.0.lock().unwrap().iter().rev().cloned().collect()
+    }
+}
+
+/// Raises notifications. Each notification is recorded in the history
+/// and sent as a `UserEvent::Notified`, so that it can be shown.
+pub struct Notifier {
+    history: Notifications,
+    user_event: Sender<UserEvent>,
+}
+
+impl Notifier {
+    pub fn new(user_event: Sender<UserEvent>) -> Self {
+        Notifier {
+            history: Notifications::default(),
+            user_event,
+        }
+    }
+
+    pub fn notify(&self, severity: Severity, message: String) {
+        let notification = Notification {
+            severity,
+            message,
+            time: Instant::now(),
+        };
+        self.history.push(notification.clone());
+        let _ = self.user_event.send(UserEvent::Notified(notification));
+    }
+
+    /// Returns a handle to the history of notifications
+    pub fn history(&self) -> Notifications {
+        self.history.clone()
+    }
+}
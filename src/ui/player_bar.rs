@@ -1,11 +1,18 @@
 use phosphorus_core::song::SongDetails;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use tui_realm_stdlib::{Container, Label, Phantom, ProgressBar};
 use tuirealm::{
+    command::{Cmd, CmdResult},
+    event::{MouseButton, MouseEvent, MouseEventKind},
     props::{Alignment, BorderSides, Borders, Color, Layout},
-    tui::layout::{Constraint, Direction},
-    AttrValue, Attribute, Component, Event, MockComponent,
+    tui::layout::{Constraint, Direction, Rect},
+    AttrValue, Attribute, Component, Event, Frame, MockComponent, State as CState,
+};
+
+use crate::{
+    config::RepeatMode,
+    player::{Position, State},
 };
 
 use super::{event::UserEvent, AppMsg};
@@ -14,6 +21,8 @@ const LEFT_LABEL: usize = 1;
 const CURRENT_TIME: usize = 2;
 const PROGRESS_INDICATOR: usize = 4;
 const LIMIT_TIME: usize = 6;
+const VOLUME: usize = 7;
+const QUEUE_MODES: usize = 9;
 
 type Formatter = fn(&Duration) -> String;
 
@@ -37,10 +46,40 @@ fn long_formatter(duration: &Duration) -> String {
     format!("\n{}:{:02}:{:02}", hours, mins, secs)
 }
 
-#[derive(MockComponent)]
+/// Label telling which queue modes are active
+fn format_queue_modes(shuffle: bool, repeat: RepeatMode) -> String {
+    let repeat = match repeat {
+        RepeatMode::Off => "",
+        RepeatMode::All => "Repeat",
+        RepeatMode::One => "Repeat one",
+    };
+    match (shuffle, repeat.is_empty()) {
+        (true, true) => String::from("\nShuffle"),
+        (true, false) => format!("\nShuffle, {}", repeat),
+        (false, _) => format!("\n{}", repeat),
+    }
+}
+
+/// Label for the volume gauge
+fn format_volume(level: u8, muted: bool) -> String {
+    if muted {
+        String::from("Muted")
+    } else {
+        format!("Vol {}%", level)
+    }
+}
+
 pub struct PlayerBar {
     component: Container,
-    timing: Option<Instant>,
+    /// Area the bar has been drawn in the last time
+    area: Rect,
+    /// Reproduction position, as reported by the player
+    position: Position,
+    /// Duration of the current song, if a song is loaded and
+    /// its duration is known
+    duration: Option<Duration>,
+    /// Tells whether a song is loaded in the player
+    loaded: bool,
     formatter: Formatter,
 }
 
@@ -60,8 +99,8 @@ impl PlayerBar {
             )),
         );
 
-        // Initialize a timer for tracking reproduction state
-        self.timing = Some(Instant::now());
+        self.loaded = true;
+        self.duration = details.duration().cloned();
 
         // Select the appropriate formatter function according to
         // song duration
@@ -85,7 +124,7 @@ impl PlayerBar {
 
         children.get_mut(CURRENT_TIME).unwrap().attr(
             Attribute::Text,
-            AttrValue::String((self.formatter)(&self.timing.unwrap().elapsed())),
+            AttrValue::String((self.formatter)(&Duration::ZERO)),
         );
         children.get_mut(PROGRESS_INDICATOR).unwrap().attr(
             Attribute::Value,
@@ -94,10 +133,131 @@ impl PlayerBar {
             )),
         );
     }
-}
 
-impl Default for PlayerBar {
-    fn default() -> Self {
+    /// Brings the bar back to its initial state, when no song
+    /// is being reproduced
+    pub fn reset(&mut self) {
+        self.loaded = false;
+        self.duration = None;
+
+        let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
+        children.get_mut(LEFT_LABEL).unwrap().attr(
+            Attribute::Text,
+            AttrValue::String("\nNo song | Unknown".into()),
+        );
+        children
+            .get_mut(CURRENT_TIME)
+            .unwrap()
+            .attr(Attribute::Text, AttrValue::String("\n--:--".into()));
+        children
+            .get_mut(LIMIT_TIME)
+            .unwrap()
+            .attr(Attribute::Text, AttrValue::String("\n--:--".into()));
+        children.get_mut(PROGRESS_INDICATOR).unwrap().attr(
+            Attribute::Value,
+            AttrValue::Payload(tuirealm::props::PropPayload::One(
+                tuirealm::props::PropValue::F64(0.0),
+            )),
+        );
+    }
+
+    /// Reads the position from the player and updates the current
+    /// time and the progress indicator accordingly
+    fn update_progress(&mut self) {
+        let position = self.position.get();
+        let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
+        children.get_mut(CURRENT_TIME).unwrap().attr(
+            Attribute::Text,
+            AttrValue::String((self.formatter)(&position)),
+        );
+
+        if let Some(duration) = self.duration {
+            let progress = if duration.is_zero() {
+                0.0
+            } else {
+                (position.as_secs_f64() / duration.as_secs_f64()).min(1.0)
+            };
+            children.get_mut(PROGRESS_INDICATOR).unwrap().attr(
+                Attribute::Value,
+                AttrValue::Payload(tuirealm::props::PropPayload::One(
+                    tuirealm::props::PropValue::F64(progress),
+                )),
+            );
+        }
+    }
+
+    /// Updates the volume gauge
+    pub fn set_volume(&mut self, level: u8, muted: bool) {
+        let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
+        let volume = children.get_mut(VOLUME).unwrap();
+        let progress = if muted { 0.0 } else { level as f64 / 100.0 };
+        volume.attr(
+            Attribute::Value,
+            AttrValue::Payload(tuirealm::props::PropPayload::One(
+                tuirealm::props::PropValue::F64(progress),
+            )),
+        );
+        volume.attr(
+            Attribute::Text,
+            AttrValue::String(format_volume(level, muted)),
+        );
+    }
+
+    /// Updates the label showing the queue modes
+    pub fn set_queue_modes(&mut self, shuffle: bool, repeat: RepeatMode) {
+        let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
+        children.get_mut(QUEUE_MODES).unwrap().attr(
+            Attribute::Text,
+            AttrValue::String(format_queue_modes(shuffle, repeat)),
+        );
+    }
+
+    /// Maps a point of the terminal to a fraction of the progress
+    /// indicator, if the point lies on it
+    fn progress_at(&self, column: u16, row: u16) -> Option<f64> {
+        // The container draws its borders on all sides
+        let inner = Rect {
+            x: self.area.x + 1,
+            y: self.area.y + 1,
+            width: self.area.width.saturating_sub(2),
+            height: self.area.height.saturating_sub(2),
+        };
+        let progress = *Self::layout().chunks(inner).get(PROGRESS_INDICATOR)?;
+
+        if progress.width == 0
+            || column < progress.x
+            || column >= progress.x + progress.width
+            || row < progress.y
+            || row >= progress.y + progress.height
+        {
+            return None;
+        }
+
+        Some((column - progress.x) as f64 / progress.width as f64)
+    }
+
+    fn layout() -> Layout {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Length(1),      // Space between border and content
+                    Constraint::Percentage(20), // Song name and artis
+                    Constraint::Percentage(5),  // Current timing
+                    Constraint::Length(1),      // Space between current timing and progress
+                    Constraint::Percentage(50), // Progress indicator
+                    Constraint::Length(1),      // Space between progress and duration
+                    Constraint::Percentage(5),  // Duration
+                    Constraint::Percentage(10), // Volume
+                    Constraint::Length(1),      // Space between volume and queue modes
+                    Constraint::Percentage(10), // Queue modes
+                ]
+                .as_ref(),
+            )
+            .horizontal_margin(1)
+    }
+
+    pub fn new(position: Position) -> Self {
         let children: Vec<Box<dyn MockComponent>> = vec![
             Box::new(Phantom::default()),
             Box::new(
@@ -115,7 +275,19 @@ impl Default for PlayerBar {
             ),
             Box::new(Phantom::default()),
             Box::new(Label::default().alignment(Alignment::Left).text("\n--:--")),
+            Box::new(
+                ProgressBar::default()
+                    .progress(0.0)
+                    .label(&format_volume(0, false))
+                    .borders(Borders::default().sides(BorderSides::BOTTOM))
+                    .foreground(Color::LightCyan),
+            ),
             Box::new(Phantom::default()),
+            Box::new(
+                Label::default()
+                    .alignment(Alignment::Left)
+                    .text(&format_queue_modes(false, RepeatMode::Off)),
+            ),
         ];
 
         Self {
@@ -123,46 +295,77 @@ impl Default for PlayerBar {
                 .borders(Borders::default().sides(BorderSides::all()))
                 .foreground(Color::Reset)
                 .children(children)
-                .layout(
-                    Layout::default()
-                        .direction(Direction::Horizontal)
-                        .constraints(
-                            [
-                                Constraint::Length(1),      // Space between border and content
-                                Constraint::Percentage(20), // Song name and artis
-                                Constraint::Percentage(5),  // Current timing
-                                Constraint::Length(1), // Space between current timing and progress
-                                Constraint::Percentage(50), // Progress indicator
-                                Constraint::Length(1), // Space between progress and duration
-                                Constraint::Percentage(5), // Duration
-                                Constraint::Percentage(20), // Empty space on the right
-                            ]
-                            .as_ref(),
-                        )
-                        .horizontal_margin(1),
-                ),
-            timing: None,
+                .layout(Self::layout()),
+            area: Rect::default(),
+            position,
+            duration: None,
+            loaded: false,
             formatter: short_formatter,
         }
     }
 }
 
+impl MockComponent for PlayerBar {
+    fn view(&mut self, frame: &mut Frame, area: Rect) {
+        self.area = area;
+        self.component.view(frame, area);
+    }
+
+    fn query(&self, attr: Attribute) -> Option<AttrValue> {
+        self.component.query(attr)
+    }
+
+    fn attr(&mut self, attr: Attribute, value: AttrValue) {
+        self.component.attr(attr, value);
+    }
+
+    fn state(&self) -> CState {
+        self.component.state()
+    }
+
+    fn perform(&mut self, cmd: Cmd) -> CmdResult {
+        self.component.perform(cmd)
+    }
+}
+
 impl Component<AppMsg, UserEvent> for PlayerBar {
     fn on(&mut self, ev: tuirealm::Event<UserEvent>) -> Option<AppMsg> {
-        let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
-
         match ev {
             Event::User(UserEvent::PlaySong(song)) => {
                 self.set_song(song.details());
+                return Some(AppMsg::PlaybackChanged(State::Playing));
+            }
+            Event::User(UserEvent::PlaybackPaused) => {
+                return Some(AppMsg::PlaybackChanged(State::Paused));
+            }
+            Event::User(UserEvent::PlaybackResumed) => {
+                return Some(AppMsg::PlaybackChanged(State::Playing));
+            }
+            Event::User(UserEvent::PlaybackStopped) | Event::User(UserEvent::TrackEnded) => {
+                self.reset();
+                return Some(AppMsg::PlaybackChanged(State::None));
+            }
+            Event::User(UserEvent::VolumeChanged { level, muted }) => {
+                self.set_volume(level, muted);
+            }
+            Event::User(UserEvent::QueueModesChanged { shuffle, repeat }) => {
+                self.set_queue_modes(shuffle, repeat);
             }
             Event::Tick => {
-                if let Some(timer) = self.timing {
-                    let duration = timer.elapsed();
-                    if duration.as_secs() > 0 {
-                        children.get_mut(CURRENT_TIME).unwrap().attr(
-                            Attribute::Text,
-                            AttrValue::String((self.formatter)(&duration)),
-                        );
+                if self.loaded {
+                    self.update_progress();
+                    return Some(AppMsg::None);
+                }
+            }
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                ..
+            }) => {
+                if self.loaded {
+                    if let Some(ratio) = self.progress_at(column, row) {
+                        return Some(AppMsg::SeekTo(ratio));
                     }
                 }
             }
@@ -0,0 +1,342 @@
+use std::{
+    ffi::{c_char, CStr, OsStr},
+    panic::AssertUnwindSafe,
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+
+use libloading::Library;
+use phosphorus_core::{
+    plugin_manager::{query::QueryResultData, PluginManager},
+    song::Song,
+};
+
+use youtube::YouTube;
+
+use super::local_library::LOCAL_PLUGIN;
+
+/// Plugins built into the application, served through `PluginManager`
+pub const BUILTIN_PLUGINS: [&str; 1] = ["YouTube"];
+
+/// Version of the interface between the application and the plugins loaded
+/// from shared libraries. It must be bumped whenever the symbols a library
+/// has to export change.
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+
+/// Version of the compiler the application has been built with. Plugins are
+/// passed across the Rust ABI, which is only stable within the same compiler.
+pub const RUSTC_VERSION: &str = env!("PHOSPHORUS_RUSTC_VERSION");
+
+/// Version of `phosphorus_core` the application has been built with. Plugins
+/// built against another one may lay out the plugin trait differently.
+pub const CORE_VERSION: &str = env!("PHOSPHORUS_CORE_VERSION");
+
+/// Symbol of the `u32` static with which a shared library declares the
+/// interface version it implements
+const ABI_SYMBOL: &[u8] = b"PHOSPHORUS_PLUGIN_ABI\0";
+/// Symbol of the `extern "C"` function returning, as a nul-terminated string,
+/// the output of `rustc --version` for the compiler the library is built with
+const RUSTC_SYMBOL: &[u8] = b"phosphorus_plugin_rustc_version\0";
+/// Symbol of the `extern "C"` function returning, as a nul-terminated string,
+/// the version of `phosphorus_core` the library is built against
+const CORE_SYMBOL: &[u8] = b"phosphorus_plugin_core_version\0";
+/// Symbol of the function creating the plugin of a shared library. It takes
+/// no argument and returns the boxed plugin, as `register_plugin` expects it.
+const CONSTRUCTOR_SYMBOL: &[u8] = b"phosphorus_plugin_create\0";
+
+/// Shared libraries loaded so far, along with the name of their plugin.
+/// Libraries are never unloaded, as plugins created from them may be
+/// in use until the application exits.
+static LOADED: Mutex<Vec<(String, Library)>> = Mutex::new(Vec::new());
+
+const UNKNOWN_ARTIST: &'static str = "Unknown";
+const UNKNOWN_DURATION: &'static str = " - ";
+
+/// A track offered by a remote plugin. Unlike `QueryResultData`,
+/// it can be built from the search results cached on disk.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Track {
+    pub name: String,
+    pub artist: String,
+    pub duration: Duration,
+    pub url: String,
+}
+
+impl From<&QueryResultData> for Track {
+    fn from(data: &QueryResultData) -> Self {
+        Track {
+            name: data.track_name().to_string(),
+            artist: data.artist_name().to_string(),
+            duration: *data.duration(),
+            url: data.track_url().to_string(),
+        }
+    }
+}
+
+/// Formats `duration` as `mm:ss`, or `h:mm:ss` if longer than an hour
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// What a search result points to
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum ResultData {
+    /// A track offered by a remote plugin, that has to be downloaded
+    Remote(Track),
+    /// A song already in the library
+    Local(Song),
+}
+
+impl ResultData {
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            ResultData::Remote(track) => Some(track.duration),
+            ResultData::Local(song) => song.details().duration().cloned(),
+        }
+    }
+}
+
+/// A search result, along with the name of the plugin it comes from
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct SearchResult {
+    pub plugin: String,
+    pub data: ResultData,
+}
+
+impl SearchResult {
+    pub fn track_name(&self) -> &str {
+        match &self.data {
+            ResultData::Remote(track) => &track.name,
+            ResultData::Local(song) => song.details().name(),
+        }
+    }
+
+    pub fn artist_name(&self) -> &str {
+        match &self.data {
+            ResultData::Remote(track) => &track.artist,
+            ResultData::Local(song) => song.details().artist().unwrap_or(UNKNOWN_ARTIST),
+        }
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.data.duration()
+    }
+
+    pub fn duration_str(&self) -> String {
+        match &self.data {
+            ResultData::Remote(track) => format_duration(track.duration),
+            ResultData::Local(song) => song
+                .details()
+                .duration_str()
+                .unwrap_or(UNKNOWN_DURATION.into()),
+        }
+    }
+}
+
+/// Registers the built-in plugin called `name` in `manager`
+fn register(manager: &mut PluginManager, name: &str) -> Result<(), String> {
+    let result = match name {
+        "YouTube" => manager.register_plugin(Box::new(YouTube {}), "YouTube"),
+        _ => manager.register_plugin(instantiate(name)?, name),
+    };
+    result
+        .map(|_| ())
+        .map_err(|msg| format!("Plugin `{}` couldn't be registered: {}", name, msg))
+}
+
+/// Creates the plugin of the shared library loaded as `name`
+fn instantiate<P>(name: &str) -> Result<P, String> {
+    let loaded = LOADED.lock().unwrap();
+    let library = match loaded.iter().find(|(known, _)| known == name) {
+        Some((_, library)) => library,
+        None => return Err(format!("Unknown plugin `{}`", name)),
+    };
+
+    // Safety: the library declared the expected interface version, and to be
+    // built by the same compiler against the same core, when loaded
+    let constructor = unsafe { library.get::<fn() -> P>(CONSTRUCTOR_SYMBOL) }
+        .map_err(|msg| format!("Plugin `{}` can't be created: {}", name, msg))?;
+    std::panic::catch_unwind(AssertUnwindSafe(|| constructor()))
+        .map_err(|_| format!("Plugin `{}` panicked while being created", name))
+}
+
+/// Reads the version returned by the `extern "C"` function exported
+/// by `library` as `symbol`
+fn read_version(library: &Library, symbol: &[u8], what: &str) -> Result<String, String> {
+    let version = unsafe { library.get::<extern "C" fn() -> *const c_char>(symbol) }
+        .map_err(|_| format!("no {} version is declared", what))?;
+    let version = version();
+    if version.is_null() {
+        return Err(format!("no {} version is declared", what));
+    }
+    // Safety: the function returns a nul-terminated string, as the interface requires
+    let version = unsafe { CStr::from_ptr(version) }.to_string_lossy();
+    match version.trim() {
+        "" => Err(format!("no {} version is declared", what)),
+        version => Ok(version.to_string()),
+    }
+}
+
+/// Opens the shared library at `path`, checking that it implements the
+/// expected version of the plugin interface, and that it's built by the
+/// same compiler against the same `phosphorus_core` as the application.
+/// Libraries failing any check are refused, as creating their plugin
+/// would be undefined behaviour.
+fn open(path: &Path) -> Result<Library, String> {
+    // Safety: loading a library runs its initialization routines, so only
+    // the ones put in the plugins folder by the user are loaded
+    let library = unsafe { Library::new(path) }.map_err(|msg| msg.to_string())?;
+
+    let abi = unsafe { library.get::<*const u32>(ABI_SYMBOL) }
+        .map_err(|_| String::from("no interface version is declared"))?;
+    // Safety: the symbol is declared as a `u32` static by the interface
+    let abi = unsafe { **abi };
+    if abi != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "interface version {} isn't supported, {} is expected",
+            abi, PLUGIN_ABI_VERSION
+        ));
+    }
+
+    let rustc = read_version(&library, RUSTC_SYMBOL, "compiler")?;
+    if rustc != RUSTC_VERSION {
+        return Err(format!(
+            "built with {}, while the application is built with {}",
+            rustc, RUSTC_VERSION
+        ));
+    }
+    let core = read_version(&library, CORE_SYMBOL, "phosphorus_core")?;
+    if core != CORE_VERSION {
+        return Err(format!(
+            "built against phosphorus_core {}, while the application uses {}",
+            core, CORE_VERSION
+        ));
+    }
+
+    if unsafe { library.get::<*const ()>(CONSTRUCTOR_SYMBOL) }.is_err() {
+        return Err(String::from("no constructor is exported"));
+    }
+    Ok(library)
+}
+
+/// Loads the plugins in the shared libraries found in `dir`. Each plugin
+/// is named after its file, without extension and `lib` prefix. Libraries
+/// that can't be loaded are skipped, and the reasons are returned.
+pub fn load(dir: &Path) -> Vec<String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(msg) => return vec![format!("Plugins folder couldn't be read: {}", msg)],
+    };
+
+    let mut loaded = LOADED.lock().unwrap();
+    let mut errors = vec![];
+    for path in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        if path.extension() != Some(OsStr::new(std::env::consts::DLL_EXTENSION)) {
+            continue;
+        }
+        let name = match path.file_stem().and_then(OsStr::to_str) {
+            Some(stem) => stem.strip_prefix("lib").unwrap_or(stem).to_string(),
+            None => continue,
+        };
+
+        let taken = BUILTIN_PLUGINS.contains(&name.as_str())
+            || name == LOCAL_PLUGIN
+            || loaded.iter().any(|(known, _)| *known == name);
+        if taken {
+            errors.push(format!(
+                "Plugin `{}` already exists, {} is skipped",
+                name,
+                path.display()
+            ));
+            continue;
+        }
+
+        match open(&path) {
+            Ok(library) => loaded.push((name, library)),
+            Err(msg) => errors.push(format!(
+                "Plugin {} couldn't be loaded: {}",
+                path.display(),
+                msg
+            )),
+        }
+    }
+    errors
+}
+
+/// Names of the plugins to register in `PluginManager`: the given ones, or
+/// all the built-in and loaded ones if `plugins` is empty. The local plugin
+/// is left out, as it's always available.
+pub fn enabled(plugins: &[String]) -> Vec<String> {
+    if plugins.is_empty() {
+        let loaded = LOADED.lock().unwrap();
+        BUILTIN_PLUGINS
+            .iter()
+            .map(|name| name.to_string())
+            .chain(loaded.iter().map(|(name, _)| name.clone()))
+            .collect()
+    } else {
+        plugins
+            .iter()
+            .filter(|name| *name != LOCAL_PLUGIN)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Creates a plugin manager with only the plugin called `name`
+/// registered, and set as default
+pub fn single(name: &str) -> Result<PluginManager, String> {
+    let mut manager = PluginManager::new();
+    register(&mut manager, name)?;
+    if let Err(msg) = manager.set_default(name) {
+        return Err(format!("Plugin `{}` can't be used: {}", name, msg));
+    }
+    Ok(manager)
+}
+
+/// Creates a plugin manager with the given plugins registered, or all the
+/// built-in ones if `plugins` is empty. The first registered plugin is set
+/// as default.
+///
+/// Plugins that can't be registered are skipped. An error is returned only
+/// if no plugin at all could be registered.
+pub fn plugin_manager(plugins: &[String]) -> Result<PluginManager, String> {
+    let mut manager = PluginManager::new();
+    let mut default = None;
+    let mut errors = vec![];
+    for name in enabled(plugins) {
+        match register(&mut manager, &name) {
+            Ok(_) => {
+                default.get_or_insert(name);
+            }
+            Err(msg) => errors.push(msg),
+        }
+    }
+
+    let default = match default {
+        Some(default) => default,
+        None => {
+            return Err(format!(
+                "An error occured while trying to register plugins:\n{}",
+                errors.join("\n")
+            ))
+        }
+    };
+    if let Err(msg) = manager.set_default(&default) {
+        return Err(format!(
+            "An error occured while trying to set the default plugin\n{}",
+            msg
+        ));
+    }
+
+    Ok(manager)
+}
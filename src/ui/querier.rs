@@ -1,129 +1,763 @@
-use std::sync::mpsc::Sender;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
-use phosphorus_core::plugin_manager::{query::QueryInfo, PluginManager};
+use super::{
+    event::UserEvent,
+    local_library::{Library, LOCAL_PLUGIN},
+    plugin_registry::{self, ResultData, SearchResult, Track},
+    search_cache::SearchCache,
+    search_query::SearchQuery,
+};
 
-use super::event::UserEvent;
-use phosphorus_core::plugin_manager::downloader::ProgressFollowerFn;
-use youtube::YouTube;
+/// How often a running search checks whether it has been cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Number of results a plugin able to page sends at a time. The
+/// following ones are sent on request, as the next page.
+const PAGE_SIZE: usize = 20;
+
+/// Results whose durations differ by at most this much may be duplicates
+const DUPLICATE_DURATION_TOLERANCE: Duration = Duration::from_secs(3);
 
 pub enum Message {
-    Search(QueryInfo),
-    Download(String, String, ProgressFollowerFn),
+    /// Serves the given page of the query with the given id, giving up at
+    /// the given instant. If the flag is set, cached results are ignored.
+    Search(u64, SearchQuery, Instant, bool, usize),
     Quit,
 }
 
+/// Outcome of a search on a single plugin
+enum Outcome {
+    /// Results found, and whether a further page can be requested
+    Found(Vec<ResultData>, bool),
+    Failed(String),
+    TimedOut,
+}
+
+impl Outcome {
+    /// Results of a remote plugin. Plugins can't be asked for a further
+    /// page, so all the results they found are sent at once.
+    fn remote(tracks: Vec<Track>) -> Self {
+        Outcome::Found(tracks.into_iter().map(ResultData::Remote).collect(), false)
+    }
+
+    /// Keeps only the results respecting the duration bounds of `query`
+    fn filter(self, query: &SearchQuery) -> Self {
+        match self {
+            Outcome::Found(data, more) => Outcome::Found(
+                data.into_iter()
+                    .filter(|data| query.admits(data.duration()))
+                    .collect(),
+                more,
+            ),
+            outcome => outcome,
+        }
+    }
+}
+
+/// Reduces a track or artist name to its lowercase alphanumeric
+/// characters, so that slightly different spellings compare equal
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Tells whether two durations are close enough to belong to the same
+/// track. Unknown durations are close to anything.
+fn close(a: Option<Duration>, b: Option<Duration>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.max(b) - a.min(b) <= DUPLICATE_DURATION_TOLERANCE,
+        _ => true,
+    }
+}
+
+/// State of the search being served, shared by the workers
+/// to merge their results
+#[derive(Default)]
+struct Search {
+    id: u64,
+    /// Number of plugins that haven't answered yet
+    pending: usize,
+    /// Normalized track name, artist name and duration of the
+    /// results sent so far
+    seen: Vec<(String, String, Option<Duration>)>,
+    /// Number of results sent before the page being served
+    shown: usize,
+    /// Plugins that can send a further page of results
+    pageable: Vec<String>,
+    /// Reasons why some plugins failed
+    errors: Vec<String>,
+    /// Plugins that didn't answer in time
+    timed_out: Vec<String>,
+    /// Library to search, along with the query, if the plugins fail
+    fallback: Option<(Library, SearchQuery)>,
+}
+
+impl Search {
+    fn new(id: u64, pending: usize, fallback: Option<(Library, SearchQuery)>) -> Self {
+        Search {
+            id,
+            pending,
+            fallback,
+            ..Default::default()
+        }
+    }
+
+    /// Starts serving the next page of the same query, with the given id.
+    /// Results already sent are still left out.
+    fn next_page(&mut self, id: u64, pending: usize) {
+        self.id = id;
+        self.pending = pending;
+        self.shown = self.seen.len();
+        self.pageable.clear();
+        self.errors.clear();
+        self.timed_out.clear();
+        self.fallback = None;
+    }
+
+    /// Tells whether `result` isn't a near-duplicate of a result
+    /// already sent, recording it if so
+    fn admit(&mut self, result: &SearchResult) -> bool {
+        let track = normalize(result.track_name());
+        let artist = normalize(result.artist_name());
+        let duration = result.duration();
+        let duplicate = self
+            .seen
+            .iter()
+            .any(|(seen_track, seen_artist, seen_duration)| {
+                *seen_track == track && *seen_artist == artist && close(*seen_duration, duration)
+            });
+        if !duplicate {
+            self.seen.push((track, artist, duration));
+        }
+        !duplicate
+    }
+
+    /// Sends the results in `data`, found by `plugin`, leaving out those
+    /// already sent. The first ones are sent as `UserEvent::QueryResult`,
+    /// the following ones as `UserEvent::QueryResultExtended`.
+    fn send(&mut self, plugin: &str, data: Vec<ResultData>, user_event: &Sender<UserEvent>) {
+        let results: Vec<SearchResult> = data
+            .into_iter()
+            .map(|data| SearchResult {
+                plugin: plugin.to_string(),
+                data,
+            })
+            .filter(|result| self.admit(result))
+            .collect();
+        if results.is_empty() {
+            return;
+        }
+
+        // `seen` already includes the new results
+        let event = if self.seen.len() == results.len() {
+            UserEvent::QueryResult(results)
+        } else {
+            UserEvent::QueryResultExtended(results)
+        };
+        let _ = user_event.send(event);
+    }
+
+    /// Records the outcome of the search on `plugin`. Fresh results are
+    /// sent right away. Once every plugin has answered, the search is
+    /// reported as completed.
+    fn merge(
+        &mut self,
+        plugin: &str,
+        outcome: Outcome,
+        user_event: &Sender<UserEvent>,
+        timeout: Duration,
+    ) {
+        self.pending -= 1;
+        match outcome {
+            Outcome::Found(data, more) => {
+                if more {
+                    self.pageable.push(plugin.to_string());
+                }
+                self.send(plugin, data, user_event);
+            }
+            Outcome::Failed(msg) => self.errors.push(format!("{}: {}", plugin, msg)),
+            Outcome::TimedOut => self.timed_out.push(plugin.to_string()),
+        }
+
+        if self.pending > 0 {
+            return;
+        }
+
+        // When the plugins fail, the library may still have what's wanted
+        let failed = !self.errors.is_empty() || !self.timed_out.is_empty();
+        if self.seen.is_empty() && failed {
+            if let Some((library, query)) = self.fallback.take() {
+                let songs = library.search(&query.terms());
+                let data = songs
+                    .into_iter()
+                    .map(ResultData::Local)
+                    .filter(|data| query.admits(data.duration()))
+                    .take(PAGE_SIZE)
+                    .collect();
+                self.send(LOCAL_PLUGIN, data, user_event);
+            }
+        }
+
+        if !self.seen.is_empty() {
+            // Plugins that didn't contribute are reported on their own
+            for msg in self.errors.drain(..) {
+                let _ =
+                    user_event.send(UserEvent::PluginError(format!("Search failed on {}", msg)));
+            }
+            for plugin in self.timed_out.drain(..) {
+                let _ = user_event.send(UserEvent::PluginError(format!(
+                    "Search timed out on {}",
+                    plugin
+                )));
+            }
+            let _ = user_event.send(UserEvent::QueryCompleted);
+            // The last page requested has been served
+            if self.shown > 0 && self.pageable.is_empty() {
+                let _ = user_event.send(UserEvent::NoMoreResults);
+            }
+        } else if !self.errors.is_empty() {
+            let _ = user_event.send(UserEvent::QueryError(self.errors.join("; ")));
+        } else if !self.timed_out.is_empty() {
+            let _ = user_event.send(UserEvent::QueryTimedOut(timeout.as_secs()));
+        } else {
+            let _ = user_event.send(UserEvent::QueryResult(vec![]));
+            let _ = user_event.send(UserEvent::QueryCompleted);
+        }
+    }
+}
+
+/// State shared by the querier and its internal workers
+#[derive(Clone)]
+struct Shared {
+    /// Id of the last query sent. A query whose id doesn't match
+    /// is abandoned by the workers.
+    current: Arc<AtomicU64>,
+    /// Search being served, whose results are merged by the workers
+    search: Arc<Mutex<Search>>,
+    timeout: Duration,
+    user_event: Sender<UserEvent>,
+}
+
+impl Shared {
+    /// Serves the queries received through `rx` on behalf of `plugin`,
+    /// until told to quit. `search` returns `None` for queries
+    /// abandoned while being served. Its last arguments tell whether
+    /// cached results must be ignored, and the page requested.
+    fn serve(
+        &self,
+        plugin: &str,
+        rx: Receiver<Message>,
+        mut search: impl FnMut(u64, &SearchQuery, Instant, bool, usize) -> Option<Outcome>,
+    ) {
+        while let Ok(Message::Search(id, query, deadline, fresh, page)) = rx.recv() {
+            // Queries replaced by a newer one are skipped
+            if self.current.load(Ordering::SeqCst) != id {
+                continue;
+            }
+
+            let outcome = match search(id, &query, deadline, fresh, page) {
+                Some(outcome) => outcome.filter(&query),
+                None => continue,
+            };
+
+            // Abandoned queries are not reported, even if their
+            // result arrived in the meantime
+            let mut state = self.search.lock().unwrap();
+            if state.id == id && self.current.load(Ordering::SeqCst) == id {
+                state.merge(plugin, outcome, &self.user_event, self.timeout);
+            }
+        }
+    }
+}
+
 /// Implements an interface to interact with `plugin_manager`. `Querier`
-/// will create and internal worker that will serve queries.
+/// will create an internal worker for each plugin, that will serve queries.
 ///
 /// In particular, queries can be sent to querier using its `query` method.
-/// That method will pass the query to the internal worker, who's then
-/// responsible for passing it to the `plugin_manager` and handle the result.
+/// That method will pass the query to the internal workers, who are then
+/// responsible for passing it to their `plugin_manager` and handle the result.
 /// The result will be sent to the opposite receiving half of the channel
 /// provided in `new`. So, the opposite half of `user_event`.
+///
+/// Only the last query sent is served: sending a new one, or calling
+/// `cancel`, abandons the one in flight. Queries lasting longer than
+/// the timeout provided in `new` are abandoned too.
+///
+/// Queries are served by the default plugin, which can be changed
+/// with `set_default`. Every change is reported as a
+/// `UserEvent::DefaultPluginChanged`, the initial choice included.
+/// Federated queries are served by all the plugins in parallel
+/// instead, and their results are merged leaving out near-duplicates.
+///
+/// Plugins able to page, like the local one, send a page of results at
+/// first. The following pages of the last query are requested with `more`.
+/// Remote plugins send all their results at once instead.
+///
+/// Results found by remote plugins are cached on disk, and served from
+/// there until they get outdated, or a fresh query is sent. Outdated
+/// results are still served when the plugin can't be reached.
+///
+/// Besides the remote plugins, the local plugin is always available:
+/// it searches the songs already downloaded, even offline, and it's
+/// used as a fallback when the other plugins fail.
 pub struct Querier {
-    /// Sending half of the communication channel with the internal worker.
-    tx: Sender<Message>,
+    /// Names of the registered plugins, along with the sending half of
+    /// the communication channel with their internal worker.
+    workers: Vec<(String, Sender<Message>)>,
+    shared: Shared,
+    /// Songs searched by the local plugin
+    library: Library,
+    /// Names of the registered plugins, in registration order
+    plugins: Vec<String>,
+    /// Index of the plugin serving queries that aren't federated
+    default: usize,
+    /// Last query sent
+    last: Option<SearchQuery>,
+    /// Page of the last query requested most recently
+    page: usize,
 }
 
 impl Querier {
-    pub fn new(user_event: Sender<UserEvent>) -> Result<Self, ()> {
-        // Channel used for communication between querier and its internal
-        // worker. Querier APIs will send `crate::Message`s to internal
-        // worker who will receive them using `internal_rx`.
-        let (internal_tx, internal_rx) = std::sync::mpsc::channel();
+    /// Registers the given `plugins`, or all the built-in ones if empty, and
+    /// makes `default` the one serving queries. If `default` isn't given, or
+    /// it couldn't be registered, the first registered plugin is used.
+    /// The local plugin searches `library`, the remote ones store their
+    /// results in `cache`.
+    pub fn new(
+        user_event: Sender<UserEvent>,
+        timeout: Duration,
+        plugins: &[String],
+        default: Option<&str>,
+        library: Library,
+        cache: SearchCache,
+    ) -> Self {
+        let shared = Shared {
+            current: Arc::new(AtomicU64::new(0)),
+            search: Arc::new(Mutex::new(Search::default())),
+            timeout,
+            user_event: user_event.clone(),
+        };
 
-        // Channel used to check the correct creation and setup of the
-        // internal woker. During its creation, the internal worker will
-        // send `None` through the channel if some error occured. If everything
-        // went as fine, one `Some(())` is sent at the end.
-        let (tmp_tx, tmp_rx) = std::sync::mpsc::channel();
+        let mut workers = vec![];
+        let mut errors = vec![];
+        for name in plugin_registry::enabled(plugins) {
+            // Channel used for communication between querier and the internal
+            // worker. Querier APIs will send `crate::Message`s to the internal
+            // worker who will receive them using `internal_rx`.
+            let (internal_tx, internal_rx) = std::sync::mpsc::channel();
 
-        // Internal worker
-        let _thread = std::thread::spawn(move || {
-            // Creates the plugin manager and sets plugins
-            let mut manager = PluginManager::new();
-            if let Err(msg) = manager.register_plugin(Box::new(YouTube {}), "YouTube") {
-                eprintln!(
-                    "An error occured while trying to register a plugin:\n{}",
-                    msg
-                );
-                let _ = tmp_tx.send(None);
-                std::process::exit(1);
-            }
-            if let Err(msg) = manager.set_default("YouTube") {
-                eprintln!(
-                    "An error occured while trying to set the default plugin\n{}",
-                    msg
-                );
-                let _ = tmp_tx.send(None);
-                std::process::exit(1);
-            }
+            // Channel used to check the correct creation and setup of the
+            // internal woker. During its creation, the internal worker will
+            // send the error through the channel if some occured. If everything
+            // went as fine, one `Ok(())` is sent at the end.
+            let (tmp_tx, tmp_rx) = std::sync::mpsc::channel();
 
-            let runtime = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build();
+            let worker_name = name.clone();
+            let worker_shared = shared.clone();
+            let cache = cache.clone();
 
-            let runtime = match runtime {
-                Ok(runtime) => runtime,
-                Err(_) => {
-                    eprintln!("Error in runtime creation");
-                    let _ = tmp_tx.send(None);
-                    std::process::exit(1);
-                }
-            };
+            // Internal worker
+            let _thread = std::thread::spawn(move || {
+                let name = worker_name;
 
-            // Everythig was fine and the plugin manager has been created
-            let _ = tmp_tx.send(Some(()));
-
-            loop {
-                let message = internal_rx.recv().unwrap_or(Message::Quit);
-                match message {
-                    Message::Search(query) => {
-                        let result = runtime.block_on(manager.query(query));
-                        let _ = match result {
-                            Ok(result) => user_event.send(UserEvent::QueryResult(result)),
-                            Err(error) => user_event.send(UserEvent::QueryError(error.to_string())),
-                        };
+                // Creates the plugin manager serving this plugin only
+                let manager = match plugin_registry::single(&name) {
+                    Ok(manager) => manager,
+                    Err(msg) => {
+                        let _ = tmp_tx.send(Err(msg));
+                        return;
                     }
-                    Message::Download(url, file_name, progress_follower) => {
-                        let result =
-                            runtime.block_on(manager.download(&url, &file_name, progress_follower));
-                        let _ = match result {
-                            Ok(_) => Ok(()),
-                            Err(error) => {
-                                user_event.send(UserEvent::DownloadError(error.to_string()))
-                            }
-                        };
+                };
+
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(msg) => {
+                        let _ = tmp_tx.send(Err(format!("Error in runtime creation: {}", msg)));
+                        return;
                     }
-                    Message::Quit => break,
                 };
+
+                // Everythig was fine and the plugin manager has been created
+                let _ = tmp_tx.send(Ok(()));
+
+                let current = worker_shared.current.clone();
+                // Remote plugins are only ever asked for the first page
+                worker_shared.serve(&name, internal_rx, |id, query, deadline, fresh, _| {
+                    let cached = cache.get(&name, &query.text);
+                    if let (false, Some((tracks, false))) = (fresh, &cached) {
+                        return Some(Outcome::remote(tracks.clone()));
+                    }
+
+                    let result = runtime.block_on(async {
+                        let deadline = tokio::time::Instant::from_std(deadline);
+                        tokio::select! {
+                            result = manager.query(query.info()) => Some(
+                                result.map_err(|error| Outcome::Failed(error.to_string()))
+                            ),
+                            _ = tokio::time::sleep_until(deadline) => Some(Err(Outcome::TimedOut)),
+                            _ = replaced(&current, id) => None,
+                        }
+                    })?;
+
+                    match result {
+                        Ok(result) => {
+                            let tracks: Vec<Track> =
+                                result.data().iter().map(Track::from).collect();
+                            cache.put(&name, &query.text, &tracks);
+                            Some(Outcome::remote(tracks))
+                        }
+                        // Outdated results are better than none when offline
+                        Err(outcome) => match cached {
+                            Some((tracks, _)) => Some(Outcome::remote(tracks)),
+                            None => Some(outcome),
+                        },
+                    }
+                });
+            });
+
+            // If `Ok(())` is received, the plugin manager has been created successfully
+            // and the worker will be able to process queries. Otherwise, the worker is
+            // shutdown and the plugin is left out.
+            match tmp_rx.recv() {
+                Ok(Ok(())) => workers.push((name, internal_tx)),
+                Ok(Err(msg)) => errors.push(msg),
+                Err(_) => errors.push(format!("Plugin `{}` couldn't be started", name)),
             }
+        }
+        for msg in errors {
+            let _ = user_event.send(UserEvent::PluginError(msg));
+        }
+
+        // The local plugin needs nothing but the library, so it's always available
+        let (internal_tx, internal_rx) = std::sync::mpsc::channel();
+        let worker_shared = shared.clone();
+        let worker_library = library.clone();
+        let _thread = std::thread::spawn(move || {
+            // The library is searched in memory, so any page can be served
+            worker_shared.serve(LOCAL_PLUGIN, internal_rx, |_, query, _, _, page| {
+                let mut data: Vec<ResultData> = worker_library
+                    .search(&query.terms())
+                    .into_iter()
+                    .map(ResultData::Local)
+                    .filter(|data| query.admits(data.duration()))
+                    .skip(page * PAGE_SIZE)
+                    .take(PAGE_SIZE + 1)
+                    .collect();
+                let more = data.len() > PAGE_SIZE;
+                data.truncate(PAGE_SIZE);
+                Some(Outcome::Found(data, more))
+            });
         });
+        workers.push((LOCAL_PLUGIN.to_string(), internal_tx));
+
+        let plugins: Vec<String> = workers.iter().map(|(name, _)| name.clone()).collect();
+        let default = default
+            .and_then(|default| plugins.iter().position(|name| name == default))
+            .unwrap_or(0);
+        let _ = user_event.send(UserEvent::DefaultPluginChanged(plugins[default].clone()));
+
+        Self {
+            workers,
+            shared,
+            library,
+            plugins,
+            default,
+            last: None,
+            page: 0,
+        }
+    }
+
+    /// Sends a query to the internal workers who will serve it, abandoning
+    /// the one being served, if any. If `federated`, the query is served
+    /// by all the plugins, otherwise by the default one only. If `fresh`,
+    /// cached results are ignored and the plugins are always asked.
+    ///
+    /// `query` may hold fields, as described in `SearchQuery`. If it's
+    /// malformed, it's searched as it is.
+    pub fn query(&mut self, query: &str, federated: bool, fresh: bool) {
+        let query = SearchQuery::parse_or_raw(query);
+        let id = self.shared.current.fetch_add(1, Ordering::SeqCst) + 1;
+        let targets: Vec<usize> = if federated {
+            (0..self.workers.len()).collect()
+        } else {
+            vec![self.default]
+        };
 
-        // If `Some(())` is received, the plugin manager has been created successfully
-        // and the worker will be able to process queries. Otherwise, the worker is
-        // shutdown and the caller must be informed of the error.
-        if let Ok(Some(())) = tmp_rx.recv() {
-            return Ok(Self { tx: internal_tx });
+        // The library is searched as a fallback, unless it's searched anyway
+        let fallback = if targets
+            .iter()
+            .any(|index| self.workers[*index].0 == LOCAL_PLUGIN)
+        {
+            None
+        } else {
+            Some((self.library.clone(), query.clone()))
+        };
+
+        // Results of the previous query are ignored from now on
+        *self.shared.search.lock().unwrap() = Search::new(id, targets.len(), fallback);
+        self.last = Some(query);
+        self.page = 0;
+        self.send(id, &targets, fresh);
+    }
+
+    /// Requests the next page of results of the last query, from the
+    /// plugins that have more to send. Nothing is requested while the
+    /// query is being served. If no plugin has more to send, it's reported
+    /// with `UserEvent::NoMoreResults`. Returns whether the page has been
+    /// requested.
+    pub fn more(&mut self) -> bool {
+        if self.last.is_none() {
+            return false;
+        }
+
+        let mut search = self.shared.search.lock().unwrap();
+        let serving = search.pending > 0 && search.id == self.shared.current.load(Ordering::SeqCst);
+        if serving {
+            return false;
         }
 
-        Err(())
+        let targets: Vec<usize> = self
+            .workers
+            .iter()
+            .enumerate()
+            .filter(|(_, (name, _))| search.pageable.contains(name))
+            .map(|(index, _)| index)
+            .collect();
+        if targets.is_empty() {
+            let _ = self.shared.user_event.send(UserEvent::NoMoreResults);
+            return false;
+        }
+
+        let id = self.shared.current.fetch_add(1, Ordering::SeqCst) + 1;
+        search.next_page(id, targets.len());
+        drop(search);
+
+        self.page += 1;
+        self.send(id, &targets, false);
+        true
     }
 
-    /// Sends a query to the internal worker who will serve it.
-    pub fn query(&self, query: QueryInfo) {
-        let _ = self.tx.send(Message::Search(query));
+    /// Sends the current page of the last query to the workers at `targets`
+    fn send(&self, id: u64, targets: &[usize], fresh: bool) {
+        let query = match &self.last {
+            Some(query) => query,
+            None => return,
+        };
+        let deadline = Instant::now() + self.shared.timeout;
+        for index in targets {
+            let (_, tx) = &self.workers[*index];
+            let _ = tx.send(Message::Search(
+                id,
+                query.clone(),
+                deadline,
+                fresh,
+                self.page,
+            ));
+        }
     }
 
-    pub fn download(&self, url: String, file_name: String, progress_follower: ProgressFollowerFn) {
-        let _ = self
-            .tx
-            .send(Message::Download(url, file_name, progress_follower));
+    /// Abandons the query being served, if any. Abandoned
+    /// queries don't produce any `UserEvent`.
+    pub fn cancel(&self) {
+        self.shared.current.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Makes the plugin called `name` the one serving the next queries
+    pub fn set_default(&mut self, name: String) {
+        let event = match self.plugins.iter().position(|plugin| *plugin == name) {
+            Some(index) => {
+                self.default = index;
+                UserEvent::DefaultPluginChanged(name)
+            }
+            None => UserEvent::PluginError(format!("Plugin `{}` isn't registered", name)),
+        };
+        let _ = self.shared.user_event.send(event);
+    }
+
+    /// Returns the name of the plugin serving queries that aren't federated
+    pub fn default_plugin(&self) -> &str {
+        &self.plugins[self.default]
+    }
+
+    /// Returns the names of the registered plugins, in registration order
+    pub fn plugins(&self) -> &[String] {
+        &self.plugins
     }
 }
 
 impl Drop for Querier {
     fn drop(&mut self) {
-        let _ = self.tx.send(Message::Quit);
+        for (_, tx) in &self.workers {
+            let _ = tx.send(Message::Quit);
+        }
+    }
+}
+
+/// Resolves once the query with the given id has been replaced
+/// by a newer one or cancelled
+async fn replaced(current: &AtomicU64, id: u64) {
+    while current.load(Ordering::SeqCst) == id {
+        tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+
+    fn track(name: &str, artist: &str, secs: u64) -> ResultData {
+        ResultData::Remote(Track {
+            name: name.to_string(),
+            artist: artist.to_string(),
+            duration: Duration::from_secs(secs),
+            url: format!("https://example.com/{}", name),
+        })
+    }
+
+    fn result(data: ResultData) -> SearchResult {
+        SearchResult {
+            plugin: String::from("YouTube"),
+            data,
+        }
+    }
+
+    #[test]
+    fn admits_near_duplicates_only_once() {
+        let mut search = Search::new(1, 1, None);
+        assert!(search.admit(&result(track("Around the World", "Daft Punk", 240))));
+        // Spelling and a duration within the tolerance don't matter
+        assert!(!search.admit(&result(track("around the world!", "DAFT PUNK", 243))));
+        assert!(!search.admit(&result(track("Around the World", "Daft Punk", 237))));
+        // A longer version is another track
+        assert!(search.admit(&result(track("Around the World", "Daft Punk", 244))));
+        assert!(search.admit(&result(track("Around the World", "Other", 240))));
+        assert_eq!(search.seen.len(), 3);
+    }
+
+    #[test]
+    fn sends_the_first_batch_as_a_new_result() {
+        let (tx, rx) = channel();
+        let mut search = Search::new(1, 2, None);
+        let timeout = Duration::from_secs(10);
+
+        search.merge(
+            "YouTube",
+            Outcome::Found(vec![track("One", "A", 100), track("Two", "B", 200)], false),
+            &tx,
+            timeout,
+        );
+        match rx.try_recv() {
+            Ok(UserEvent::QueryResult(results)) => assert_eq!(results.len(), 2),
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        search.merge(
+            "Other",
+            Outcome::Found(
+                vec![track("two", "b", 201), track("Three", "C", 300)],
+                false,
+            ),
+            &tx,
+            timeout,
+        );
+        match rx.try_recv() {
+            Ok(UserEvent::QueryResultExtended(results)) => {
+                assert_eq!(results.len(), 1);
+                assert_eq!(results[0].track_name(), "Three");
+                assert_eq!(results[0].plugin, "Other");
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+        assert!(matches!(rx.try_recv(), Ok(UserEvent::QueryCompleted)));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn reports_failures_once_every_plugin_answered() {
+        let (tx, rx) = channel();
+        let mut search = Search::new(1, 2, None);
+        let timeout = Duration::from_secs(10);
+
+        search.merge(
+            "YouTube",
+            Outcome::Failed(String::from("offline")),
+            &tx,
+            timeout,
+        );
+        assert!(rx.try_recv().is_err());
+        search.merge("Other", Outcome::TimedOut, &tx, timeout);
+        match rx.try_recv() {
+            Ok(UserEvent::QueryError(msg)) => assert_eq!(msg, "YouTube: offline"),
+            event => panic!("unexpected event: {:?}", event),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn reports_plugins_that_failed_besides_results() {
+        let (tx, rx) = channel();
+        let mut search = Search::new(1, 2, None);
+        let timeout = Duration::from_secs(10);
+
+        search.merge("YouTube", Outcome::TimedOut, &tx, timeout);
+        search.merge(
+            "Other",
+            Outcome::Found(vec![track("One", "A", 100)], false),
+            &tx,
+            timeout,
+        );
+        assert!(matches!(rx.try_recv(), Ok(UserEvent::QueryResult(_))));
+        match rx.try_recv() {
+            Ok(UserEvent::PluginError(msg)) => assert_eq!(msg, "Search timed out on YouTube"),
+            event => panic!("unexpected event: {:?}", event),
+        }
+        assert!(matches!(rx.try_recv(), Ok(UserEvent::QueryCompleted)));
+    }
+
+    #[test]
+    fn sends_further_pages_as_extensions() {
+        let (tx, rx) = channel();
+        let mut search = Search::new(1, 1, None);
+        let timeout = Duration::from_secs(10);
+
+        search.merge(
+            "YouTube",
+            Outcome::Found(vec![track("One", "A", 100)], true),
+            &tx,
+            timeout,
+        );
+        assert!(matches!(rx.try_recv(), Ok(UserEvent::QueryResult(_))));
+        assert!(matches!(rx.try_recv(), Ok(UserEvent::QueryCompleted)));
+
+        search.next_page(2, 1);
+        search.merge(
+            "YouTube",
+            Outcome::Found(vec![track("One", "A", 100), track("Two", "B", 200)], false),
+            &tx,
+            timeout,
+        );
+        match rx.try_recv() {
+            Ok(UserEvent::QueryResultExtended(results)) => {
+                assert_eq!(results.len(), 1);
+                assert_eq!(results[0].track_name(), "Two");
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+        assert!(matches!(rx.try_recv(), Ok(UserEvent::QueryCompleted)));
+        assert!(matches!(rx.try_recv(), Ok(UserEvent::NoMoreResults)));
     }
 }
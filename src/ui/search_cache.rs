@@ -0,0 +1,269 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use super::plugin_registry::Track;
+
+/// Folder, inside the cache one, holding the search results
+const SEARCH: &str = "search";
+
+/// How long cached results are considered up to date. Outdated results
+/// are used only when the plugin can't be reached.
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum size, in bytes, of the cached results. Once exceeded, the
+/// least recently stored results are removed first.
+const MAX_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Lowercase words of `query`, separated by a single space, so that
+/// trivially different queries share the same results
+fn normalize(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// 64-bit FNV-1a hash of `bytes`. Unlike the hashers of the standard
+/// library, it stays the same across Rust releases, so cache files keep
+/// being found after an update.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// Replaces the separators used in cache files, so that `field` can be stored
+fn sanitize(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Results of the searches served by remote plugins, stored on disk.
+///
+/// Each file holds the results found by one plugin for one query: a header
+/// with the plugin and the query, an empty line, then one track per line,
+/// with its fields separated by tabs.
+#[derive(Clone)]
+pub struct SearchCache {
+    dir: PathBuf,
+}
+
+impl SearchCache {
+    /// Stores results in a folder inside `cache`
+    pub fn new(cache: &Path) -> Self {
+        let dir = cache.join(SEARCH);
+        // If the folder can't be created, every lookup is a miss
+        let _ = std::fs::create_dir_all(&dir);
+        SearchCache { dir }
+    }
+
+    /// Path of the file holding the results of the normalized `query`
+    fn path(&self, plugin: &str, query: &str) -> PathBuf {
+        let key = format!("{}\0{}", plugin, query);
+        self.dir.join(format!("{:016x}", fnv1a(key.as_bytes())))
+    }
+
+    /// Returns the results found by `plugin` for `query`, if cached,
+    /// along with whether they are outdated
+    pub fn get(&self, plugin: &str, query: &str) -> Option<(Vec<Track>, bool)> {
+        let query = normalize(query);
+        let path = self.path(plugin, &query);
+        let content = std::fs::read_to_string(&path).ok()?;
+        let age = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or_default();
+
+        let (header, body) = content.split_once("\n\n")?;
+        // Files sharing the same hash are told apart by their header
+        if header != format!("plugin = {}\nquery = {}", plugin, query) {
+            return None;
+        }
+
+        let mut tracks = vec![];
+        for line in body.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..] {
+                [name, artist, duration, url] => tracks.push(Track {
+                    name: name.to_string(),
+                    artist: artist.to_string(),
+                    duration: Duration::from_millis(duration.parse().ok()?),
+                    url: url.to_string(),
+                }),
+                _ => return None,
+            }
+        }
+        Some((tracks, age > TTL))
+    }
+
+    /// Stores `tracks` as the results found by `plugin` for `query`.
+    /// Failing to store them is not an error, the cache is just missed.
+    pub fn put(&self, plugin: &str, query: &str, tracks: &[Track]) {
+        let query = normalize(query);
+        let mut content = format!("plugin = {}\nquery = {}\n\n", plugin, query);
+        for track in tracks {
+            content.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                sanitize(&track.name),
+                sanitize(&track.artist),
+                track.duration.as_millis(),
+                sanitize(&track.url)
+            ));
+        }
+
+        if std::fs::write(self.path(plugin, &query), content).is_ok() {
+            self.evict();
+        }
+    }
+
+    /// Removes the least recently stored results until the cache
+    /// fits in `MAX_SIZE`
+    fn evict(&self) {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+            })
+            .collect();
+        let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+        files.sort();
+
+        for (_, len, path) in files {
+            if size <= MAX_SIZE {
+                break;
+            }
+            if std::fs::remove_file(path).is_ok() {
+                size -= len;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    /// Cache stored in a folder of its own, removed when dropped
+    struct TestCache {
+        root: PathBuf,
+        cache: SearchCache,
+    }
+
+    impl TestCache {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "phosphorus-search-cache-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&root);
+            let cache = SearchCache::new(&root);
+            TestCache { root, cache }
+        }
+
+        /// Makes the results of `query` look stored `age` ago
+        fn age(&self, plugin: &str, query: &str, age: Duration) {
+            let path = self.cache.path(plugin, &normalize(query));
+            File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(SystemTime::now() - age)
+                .unwrap();
+        }
+    }
+
+    impl Drop for TestCache {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn track(name: &str) -> Track {
+        Track {
+            name: name.to_string(),
+            artist: String::from("Daft\tPunk"),
+            duration: Duration::from_millis(240_500),
+            url: format!("https://example.com/{}", name),
+        }
+    }
+
+    #[test]
+    fn stores_and_retrieves_results() {
+        let test = TestCache::new("roundtrip");
+        test.cache.put(
+            "YouTube",
+            "Around  the World",
+            &[track("One"), track("Two")],
+        );
+
+        let (tracks, outdated) = test.cache.get("YouTube", "around the world").unwrap();
+        assert!(!outdated);
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].name, "One");
+        // Separators are replaced, so that fields stay apart
+        assert_eq!(tracks[0].artist, "Daft Punk");
+        assert_eq!(tracks[1].duration, Duration::from_millis(240_500));
+
+        assert!(test.cache.get("Other", "around the world").is_none());
+        assert!(test.cache.get("YouTube", "around").is_none());
+    }
+
+    #[test]
+    fn reports_outdated_results() {
+        let test = TestCache::new("outdated");
+        test.cache.put("YouTube", "one", &[track("One")]);
+        test.age("YouTube", "one", TTL + Duration::from_secs(60));
+
+        let (tracks, outdated) = test.cache.get("YouTube", "one").unwrap();
+        assert!(outdated);
+        assert_eq!(tracks.len(), 1);
+    }
+
+    #[test]
+    fn ignores_files_of_other_queries() {
+        let test = TestCache::new("collision");
+        test.cache.put("YouTube", "one", &[track("One")]);
+        // As if another query had the same hash
+        std::fs::rename(
+            test.cache.path("YouTube", "one"),
+            test.cache.path("YouTube", "two"),
+        )
+        .unwrap();
+
+        assert!(test.cache.get("YouTube", "two").is_none());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_stored_results() {
+        let test = TestCache::new("evict");
+        // Lines take about 170 bytes, so each file takes between
+        // a third and half of the allowed size
+        let tracks: Vec<Track> = (0..MAX_SIZE / 400)
+            .map(|index| track(&format!("{:064}", index)))
+            .collect();
+
+        test.cache.put("YouTube", "oldest", &tracks);
+        test.age("YouTube", "oldest", Duration::from_secs(120));
+        test.cache.put("YouTube", "older", &tracks);
+        test.age("YouTube", "older", Duration::from_secs(60));
+        test.cache.put("YouTube", "newest", &tracks);
+
+        assert!(test.cache.get("YouTube", "oldest").is_none());
+        assert!(test.cache.get("YouTube", "older").is_some());
+        assert!(test.cache.get("YouTube", "newest").is_some());
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// Maximum number of queries remembered. The oldest are forgotten first.
+const MAX_ENTRIES: usize = 500;
+
+/// Queries sent so far, one per line in the history file, oldest first.
+/// It can be cloned and shared with the search bar, which recalls them.
+#[derive(Clone)]
+pub struct SearchHistory {
+    path: PathBuf,
+    entries: Arc<Mutex<Vec<String>>>,
+}
+
+impl SearchHistory {
+    /// Loads the history saved in `path`. A missing or unreadable
+    /// file means that no query has been sent yet.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = std::fs::read_to_string(&path)
+            .map(|content| {
+                content
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        SearchHistory {
+            path,
+            entries: Arc::new(Mutex::new(entries)),
+        }
+    }
+
+    /// Remembers `query` as the most recent one, and saves the history
+    pub fn push(&self, query: &str) -> std::io::Result<()> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry != query);
+        entries.push(query.to_string());
+        if entries.len() > MAX_ENTRIES {
+            let excess = entries.len() - MAX_ENTRIES;
+            entries.drain(..excess);
+        }
+        self.save(&entries)
+    }
+
+    /// Forgets every query, and saves the now empty history
+    pub fn clear(&self) -> std::io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clear();
+        self.save(&entries)
+    }
+
+    /// Returns the query sent `back` queries ago, starting from 0
+    /// for the most recent one
+    pub fn recall(&self, back: usize) -> Option<String> {
+        self.entries.lock().unwrap().iter().rev().nth(back).cloned()
+    }
+
+    /// Returns the most recent query starting with `prefix`, ignoring case
+    pub fn complete(&self, prefix: &str) -> Option<String> {
+        let prefix = prefix.to_lowercase();
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|entry| {
+                let entry = entry.to_lowercase();
+                entry.len() > prefix.len() && entry.starts_with(&prefix)
+            })
+            .cloned()
+    }
+
+    fn save(&self, entries: &[String]) -> std::io::Result<()> {
+        let content: String = entries.iter().map(|entry| format!("{}\n", entry)).collect();
+        std::fs::write(&self.path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// History saved in a file of its own, removed when dropped
+    struct TestHistory {
+        path: PathBuf,
+        history: SearchHistory,
+    }
+
+    impl TestHistory {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "phosphorus-search-history-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            let history = SearchHistory::load(path.clone());
+            TestHistory { path, history }
+        }
+    }
+
+    impl Drop for TestHistory {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    #[test]
+    fn recalls_queries_from_the_most_recent() {
+        let test = TestHistory::new("recall");
+        for query in ["one", "two", "  ", "three", "one"] {
+            test.history.push(query).unwrap();
+        }
+
+        // Blank queries are ignored, repeated ones become the most recent
+        assert_eq!(test.history.recall(0).as_deref(), Some("one"));
+        assert_eq!(test.history.recall(1).as_deref(), Some("three"));
+        assert_eq!(test.history.recall(2).as_deref(), Some("two"));
+        assert_eq!(test.history.recall(3), None);
+    }
+
+    #[test]
+    fn completes_with_the_most_recent_match() {
+        let test = TestHistory::new("complete");
+        for query in ["Daft Punk", "daft punk around the world", "Dua Lipa"] {
+            test.history.push(query).unwrap();
+        }
+
+        assert_eq!(
+            test.history.complete("DAFT").as_deref(),
+            Some("daft punk around the world")
+        );
+        assert_eq!(test.history.complete("du").as_deref(), Some("Dua Lipa"));
+        // A query isn't completed with itself
+        assert_eq!(test.history.complete("dua lipa"), None);
+        assert_eq!(test.history.complete("queen"), None);
+    }
+
+    #[test]
+    fn forgets_the_oldest_queries_past_the_limit() {
+        let test = TestHistory::new("cap");
+        for index in 0..MAX_ENTRIES + 2 {
+            test.history.push(&index.to_string()).unwrap();
+        }
+
+        let loaded = SearchHistory::load(test.path.clone());
+        assert_eq!(loaded.entries.lock().unwrap().len(), MAX_ENTRIES);
+        assert_eq!(loaded.recall(0), Some((MAX_ENTRIES + 1).to_string()));
+        assert_eq!(loaded.recall(MAX_ENTRIES - 1).as_deref(), Some("2"));
+        assert_eq!(loaded.recall(MAX_ENTRIES), None);
+    }
+
+    #[test]
+    fn saves_the_cleared_history() {
+        let test = TestHistory::new("clear");
+        test.history.push("one").unwrap();
+        test.history.clear().unwrap();
+
+        assert_eq!(test.history.recall(0), None);
+        assert_eq!(SearchHistory::load(test.path.clone()).recall(0), None);
+    }
+}
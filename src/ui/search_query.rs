@@ -0,0 +1,409 @@
+use std::time::Duration;
+
+use phosphorus_core::plugin_manager::query::QueryInfo;
+
+/// Limit on the duration of the results of a search
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DurationBound {
+    Below(Duration),
+    Above(Duration),
+}
+
+impl DurationBound {
+    /// Tells whether `duration` respects the bound.
+    /// Unknown durations respect any bound.
+    pub fn admits(&self, duration: Option<Duration>) -> bool {
+        match (self, duration) {
+            (DurationBound::Below(bound), Some(duration)) => duration < *bound,
+            (DurationBound::Above(bound), Some(duration)) => duration > *bound,
+            (_, None) => true,
+        }
+    }
+}
+
+/// Reason why a query typed in the search bar is malformed
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    /// Column, counted in characters, where the error is
+    pub column: usize,
+    pub message: String,
+}
+
+impl SyntaxError {
+    fn new(column: usize, message: impl Into<String>) -> Self {
+        SyntaxError {
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column + 1)
+    }
+}
+
+/// Parses a duration written as `4:20`, or with units as `3m30s`.
+/// Durations too long to be represented are rejected.
+fn parse_duration(text: &str) -> Option<Duration> {
+    if text.contains(':') {
+        let mut secs: u64 = 0;
+        for part in text.split(':') {
+            secs = secs.checked_mul(60)?.checked_add(part.parse().ok()?)?;
+        }
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut secs: u64 = 0;
+    let mut number = String::new();
+    for ch in text.chars() {
+        if ch.is_ascii_digit() {
+            number.push(ch);
+            continue;
+        }
+        let unit = match ch {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        secs = secs.checked_add(number.parse::<u64>().ok()?.checked_mul(unit)?)?;
+        number.clear();
+    }
+    // Every number needs its unit
+    if text.is_empty() || !number.is_empty() {
+        return None;
+    }
+    Some(Duration::from_secs(secs))
+}
+
+/// Parses a duration bound written as `<5m` or `>3:30`
+fn parse_bound(text: &str) -> Option<DurationBound> {
+    if let Some(duration) = text.strip_prefix('<') {
+        parse_duration(duration).map(DurationBound::Below)
+    } else if let Some(duration) = text.strip_prefix('>') {
+        parse_duration(duration).map(DurationBound::Above)
+    } else {
+        None
+    }
+}
+
+/// Reads the value starting at `start`, either a quoted text or a single
+/// word. Returns the value and where it ends.
+fn read_value(chars: &[char], start: usize) -> Result<(String, usize), SyntaxError> {
+    if chars.get(start) == Some(&'"') {
+        return match chars[start + 1..].iter().position(|ch| *ch == '"') {
+            Some(len) => Ok((
+                chars[start + 1..start + 1 + len].iter().collect(),
+                start + len + 2,
+            )),
+            None => Err(SyntaxError::new(start, "Unterminated quote")),
+        };
+    }
+
+    let end = chars[start..]
+        .iter()
+        .position(|ch| ch.is_whitespace())
+        .map_or(chars.len(), |len| start + len);
+    Ok((chars[start..end].iter().collect(), end))
+}
+
+/// A search as typed in the search bar. Besides plain words, it may hold
+/// fields written as `name:value`, with the value quoted if made of several
+/// words, as in `artist:"Daft Punk" title:around dur:<5m`.
+///
+/// Fields are `title` (or `track`), `artist`, `album` and `dur` (or
+/// `duration`). The latter limits the duration of the results, and can
+/// be given twice to set a range.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    /// The query as typed, identifying the search
+    pub text: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Words not belonging to any field
+    pub free: String,
+    pub duration: Vec<DurationBound>,
+}
+
+impl SearchQuery {
+    /// A query searching `text` as it is, without looking for fields
+    pub fn raw(text: &str) -> Self {
+        SearchQuery {
+            text: text.to_string(),
+            free: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Parses `text`, telling where it's malformed if so
+    pub fn parse(text: &str) -> Result<Self, SyntaxError> {
+        let query = SearchQuery::parse_fields(text, false)?;
+        if query.terms().trim().is_empty() && !query.duration.is_empty() {
+            return Err(SyntaxError::new(0, "Nothing to search, only a duration"));
+        }
+        Ok(query)
+    }
+
+    /// Parses `text` as a filter on results, which unlike a query
+    /// may hold nothing but duration bounds. Results have no album,
+    /// so filtering by `album` is refused.
+    pub fn parse_filter(text: &str) -> Result<Self, SyntaxError> {
+        SearchQuery::parse_fields(text, true)
+    }
+
+    /// Parses the fields and the free words of `text`, as a filter if `filter` is set
+    fn parse_fields(text: &str, filter: bool) -> Result<Self, SyntaxError> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut query = SearchQuery {
+            text: text.to_string(),
+            ..Default::default()
+        };
+        let mut free = vec![];
+
+        let mut pos = 0;
+        while pos < chars.len() {
+            if chars[pos].is_whitespace() {
+                pos += 1;
+                continue;
+            }
+
+            // A field name is a word directly followed by a colon
+            let name_end = chars[pos..]
+                .iter()
+                .position(|ch| !ch.is_alphabetic())
+                .map_or(chars.len(), |len| pos + len);
+            if name_end == pos || chars.get(name_end) != Some(&':') {
+                let (word, end) = read_value(&chars, pos)?;
+                free.push(word);
+                pos = end;
+                continue;
+            }
+
+            let name: String = chars[pos..name_end].iter().collect();
+            let (value, end) = read_value(&chars, name_end + 1)?;
+            if value.is_empty() {
+                return Err(SyntaxError::new(
+                    name_end + 1,
+                    format!("Missing value for `{}`", name),
+                ));
+            }
+            query.set(&name.to_lowercase(), value, pos, name_end + 1, filter)?;
+            pos = end;
+        }
+
+        query.free = free.join(" ");
+        Ok(query)
+    }
+
+    /// Parses `text`, or searches it as it is if it's malformed
+    pub fn parse_or_raw(text: &str) -> Self {
+        SearchQuery::parse(text).unwrap_or_else(|_| SearchQuery::raw(text))
+    }
+
+    /// Sets the field `name`, written at `column`, to the value at `value_column`.
+    /// Filters can't set the album.
+    fn set(
+        &mut self,
+        name: &str,
+        value: String,
+        column: usize,
+        value_column: usize,
+        filter: bool,
+    ) -> Result<(), SyntaxError> {
+        let field = match name {
+            "title" | "track" => &mut self.title,
+            "artist" => &mut self.artist,
+            "album" if filter => {
+                return Err(SyntaxError::new(
+                    column,
+                    "Results have no album, use `title` or `artist`",
+                ))
+            }
+            "album" => &mut self.album,
+            "dur" | "duration" => {
+                let bound = parse_bound(&value).ok_or_else(|| {
+                    SyntaxError::new(
+                        value_column,
+                        format!("Invalid duration `{}`, expected e.g. <5m or >3:30", value),
+                    )
+                })?;
+                self.duration.push(bound);
+                return Ok(());
+            }
+            _ => {
+                return Err(SyntaxError::new(
+                    column,
+                    format!("Unknown field `{}`", name),
+                ))
+            }
+        };
+
+        if field.is_some() {
+            return Err(SyntaxError::new(
+                column,
+                format!("Field `{}` is given twice", name),
+            ));
+        }
+        *field = Some(value);
+        Ok(())
+    }
+
+    /// Every word searched, fields included, as plain text
+    pub fn terms(&self) -> String {
+        [
+            self.title.as_deref(),
+            self.artist.as_deref(),
+            self.album.as_deref(),
+            Some(self.free.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|words| !words.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+    }
+
+    /// Builds the `QueryInfo` served by plugins. Queries without any
+    /// field are sent as raw ones.
+    pub fn info(&self) -> QueryInfo {
+        if self.title.is_none() && self.artist.is_none() && self.album.is_none() {
+            return QueryInfo::as_raw(&self.free);
+        }
+
+        let other = [self.album.as_deref(), Some(self.free.as_str())]
+            .into_iter()
+            .flatten()
+            .filter(|words| !words.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ");
+        QueryInfo::as_detailed(
+            self.title.as_deref().unwrap_or_default(),
+            self.artist.as_deref().unwrap_or_default(),
+            &other,
+        )
+    }
+
+    /// Tells whether a result lasting `duration` respects the duration bounds
+    pub fn admits(&self, duration: Option<Duration>) -> bool {
+        self.duration.iter().all(|bound| bound.admits(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fields_and_free_words() {
+        let query = SearchQuery::parse(r#"artist:"Daft Punk" title:around the world"#).unwrap();
+        assert_eq!(query.artist.as_deref(), Some("Daft Punk"));
+        assert_eq!(query.title.as_deref(), Some("around"));
+        assert_eq!(query.album, None);
+        assert_eq!(query.free, "the world");
+        assert_eq!(query.terms(), "around Daft Punk the world");
+    }
+
+    #[test]
+    fn parses_aliases_and_duration_range() {
+        let query = SearchQuery::parse("TRACK:one Album:two dur:>3:30 duration:<5m").unwrap();
+        assert_eq!(query.title.as_deref(), Some("one"));
+        assert_eq!(query.album.as_deref(), Some("two"));
+        assert_eq!(
+            query.duration,
+            vec![
+                DurationBound::Above(Duration::from_secs(210)),
+                DurationBound::Below(Duration::from_secs(300)),
+            ]
+        );
+        assert!(query.admits(Some(Duration::from_secs(240))));
+        assert!(!query.admits(Some(Duration::from_secs(360))));
+        assert!(query.admits(None));
+    }
+
+    #[test]
+    fn parses_durations_with_units() {
+        assert_eq!(parse_duration("1h2m3s"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse_duration("1:00:05"), Some(Duration::from_secs(3605)));
+        assert_eq!(parse_duration("3m30"), None);
+        assert_eq!(parse_duration("3x"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn rejects_overflowing_durations() {
+        assert_eq!(parse_duration("999999999999999999h"), None);
+        assert_eq!(parse_duration("18446744073709551615s1s"), None);
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+        assert_eq!(parse_duration("999999999999999999:00"), None);
+
+        let error = SearchQuery::parse("song dur:<999999999999999999h").unwrap_err();
+        assert_eq!(error.column, 9);
+        assert!(error.message.starts_with("Invalid duration"));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        for text in [
+            "song dur:5m",
+            "song dur:<5x",
+            "song dur:<3m30",
+            "song dur:>",
+        ] {
+            let error = SearchQuery::parse(text).unwrap_err();
+            assert_eq!(error.column, 9, "{}", text);
+            assert!(error.message.starts_with("Invalid duration"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let error = SearchQuery::parse("song year:1999").unwrap_err();
+        assert_eq!(error, SyntaxError::new(5, "Unknown field `year`"));
+        assert_eq!(error.to_string(), "Unknown field `year` at column 6");
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        let error = SearchQuery::parse(r#"artist:"Daft Punk"#).unwrap_err();
+        assert_eq!(error, SyntaxError::new(7, "Unterminated quote"));
+
+        let error = SearchQuery::parse(r#"one "two"#).unwrap_err();
+        assert_eq!(error, SyntaxError::new(4, "Unterminated quote"));
+    }
+
+    #[test]
+    fn rejects_repeated_and_empty_fields() {
+        let error = SearchQuery::parse("artist:one artist:two").unwrap_err();
+        assert_eq!(error, SyntaxError::new(11, "Field `artist` is given twice"));
+
+        let error = SearchQuery::parse("artist: one").unwrap_err();
+        assert_eq!(error, SyntaxError::new(7, "Missing value for `artist`"));
+    }
+
+    #[test]
+    fn only_filters_may_hold_nothing_but_durations() {
+        assert!(SearchQuery::parse("dur:<5m").is_err());
+        let filter = SearchQuery::parse_filter("dur:<5m").unwrap();
+        assert_eq!(filter.terms(), "");
+    }
+
+    #[test]
+    fn filters_refuse_the_album() {
+        let error = SearchQuery::parse_filter("one Album:two").unwrap_err();
+        assert_eq!(
+            error,
+            SyntaxError::new(4, "Results have no album, use `title` or `artist`")
+        );
+        assert!(SearchQuery::parse("one Album:two").is_ok());
+    }
+
+    #[test]
+    fn malformed_queries_are_searched_raw() {
+        let query = SearchQuery::parse_or_raw("song year:1999");
+        assert_eq!(query, SearchQuery::raw("song year:1999"));
+        assert_eq!(query.free, "song year:1999");
+    }
+}
@@ -0,0 +1,84 @@
+use tui_realm_stdlib::Table;
+use tuirealm::{
+    command::{Cmd, Direction, Position},
+    event::{Key, KeyEvent},
+    props::{Color, Table as TableContent, TableBuilder, TextModifiers, TextSpan},
+    Component, Event, MockComponent,
+};
+
+use crate::ui::{download_manager::DownloadEntry, event::UserEvent, AppMsg};
+
+#[derive(MockComponent)]
+pub struct DownloadsWindow {
+    component: Table,
+}
+
+impl DownloadsWindow {
+    pub fn new(entries: &[DownloadEntry]) -> Self {
+        let mut component = Table::default()
+            .highlighted_color(Color::LightYellow)
+            .scroll(true)
+            .title("Downloads", tuirealm::props::Alignment::Left)
+            .headers(&["#", "Name", "State"])
+            .highlighted_str("➤ ")
+            .row_height(1)
+            .widths(&[5, 55, 40])
+            .modifiers(TextModifiers::BOLD | TextModifiers::UNDERLINED);
+
+        if !entries.is_empty() {
+            component = component.table(Self::table(entries));
+        }
+
+        Self { component }
+    }
+
+    /// Builds the table listing `entries`, one per row. It can be used
+    /// as `Attribute::Content` to refresh the window.
+    pub fn table(entries: &[DownloadEntry]) -> TableContent {
+        if entries.is_empty() {
+            return TableContent::new();
+        }
+
+        let mut builder = TableBuilder::default();
+        for (index, entry) in entries.iter().enumerate() {
+            builder.add_col(TextSpan::new(index.to_string()).italic());
+            builder.add_col(TextSpan::new(&entry.name).italic());
+            builder.add_col(TextSpan::new(entry.state.to_string()).italic());
+            if index < entries.len() - 1 {
+                builder.add_row();
+            }
+        }
+        builder.build()
+    }
+
+    pub fn boxed(self) -> Box<Self> {
+        Box::new(self)
+    }
+}
+
+impl Component<AppMsg, UserEvent> for DownloadsWindow {
+    fn on(&mut self, ev: tuirealm::Event<UserEvent>) -> Option<AppMsg> {
+        let cmd = match ev {
+            Event::Keyboard(KeyEvent {
+                code: Key::Down, ..
+            }) => Cmd::Move(Direction::Down),
+            Event::Keyboard(KeyEvent { code: Key::Up, .. }) => Cmd::Move(Direction::Up),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageDown,
+                ..
+            }) => Cmd::Scroll(Direction::Down),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageUp, ..
+            }) => Cmd::Scroll(Direction::Up),
+            Event::Keyboard(KeyEvent {
+                code: Key::Home, ..
+            }) => Cmd::GoTo(Position::Begin),
+            Event::Keyboard(KeyEvent { code: Key::End, .. }) => Cmd::GoTo(Position::End),
+            Event::Keyboard(KeyEvent { code: Key::Tab, .. }) => return Some(AppMsg::GoNextItem),
+            _ => Cmd::None,
+        };
+
+        let _ = self.perform(cmd);
+        Some(AppMsg::None)
+    }
+}
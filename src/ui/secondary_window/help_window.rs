@@ -22,10 +22,60 @@ impl Default for HelpWindow {
         let list = vec![
             ["ESC ESC", "Terminates the application"],
             ["Esc", "Closes any secondary window open"],
+            ["Esc", "While searching, abandons the search"],
             ["Ctrl + H", "Shows the help window"],
+            ["Ctrl + D", "Shows the downloads window"],
+            ["Ctrl + N", "Shows the notifications history"],
+            ["Ctrl + P", "Shows the plugins, to choose the one searching"],
+            [
+                "Ctrl + F",
+                "Switches between searching with all plugins or one",
+            ],
+            ["Ctrl + R", "Searches again, ignoring the cached results"],
+            [
+                "Up / Down",
+                "In the search bar, recalls the previous queries",
+            ],
+            ["END", "In the search bar, accepts the completion shown"],
+            ["Ctrl + L", "Clears the search history"],
+            [
+                "field:value",
+                "Searches by title, artist or album, e.g. artist:\"Daft Punk\"",
+            ],
+            [
+                "dur:<5m",
+                "Searches songs shorter (<) or longer (>) than 5m",
+            ],
             ["ENTER", "Pressed over a playlist, shows its songs"],
+            ["ENTER", "Pressed over a song or a result, plays it"],
+            ["ENTER", "Pressed over a plugin, makes it the default one"],
+            ["D", "Pressed over a result, downloads it"],
+            ["END / L", "Pressed over the results, loads more of them"],
+            [
+                "/",
+                "Pressed over the results, types a filter, e.g. daft dur:<5m",
+            ],
+            ["Backspace", "Pressed over the results, clears the filter"],
+            [
+                "O / Shift + O",
+                "Sorts the results by the next column, or reverses",
+            ],
+            ["Delete", "Pressed over a download, cancels it"],
+            ["R", "Pressed over a failed download, retries it"],
             ["+", "Pressed over a song, pushes it in the queue"],
             ["-", "Pressed over a song in the queue, removes it"],
+            ["Ctrl + Space", "Pauses or resumes the current song"],
+            ["Ctrl + S", "Stops the current song"],
+            [". / ,", "Moves 5 seconds forward/backward in the song"],
+            ["> / <", "Moves 30 seconds forward/backward in the song"],
+            ["0 .. 9", "Jumps to the corresponding tenth of the song"],
+            ["Click", "Pressed over the progress bar, jumps there"],
+            ["Ctrl + Up/Down", "Raises or lowers the volume"],
+            ["M", "Mutes or unmutes the player"],
+            ["S", "Turns shuffle on or off"],
+            ["R", "Switches repeat mode among off, all and one"],
+            ["N", "Plays the next song in the queue"],
+            ["P", "Plays the previous song, or restarts the current one"],
         ];
         let mut builder = TableBuilder::default();
         for row in &list.as_slice()[0..&list.len() - 1] {
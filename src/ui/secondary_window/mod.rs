@@ -1,7 +1,13 @@
+mod downloads_window;
 mod help_window;
+mod notifications_window;
 mod playlist_window;
+mod plugins_window;
 mod results_window;
 
+pub use downloads_window::DownloadsWindow;
 pub use help_window::HelpWindow;
+pub use notifications_window::NotificationsWindow;
 pub use playlist_window::PlaylistWindow;
-pub use results_window::ResultsWindow;
+pub use plugins_window::PluginsWindow;
+pub use results_window::{ResultsView, ResultsWindow};
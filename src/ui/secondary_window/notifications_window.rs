@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use tui_realm_stdlib::Table;
+use tuirealm::{
+    command::{Cmd, Direction, Position},
+    event::{Key, KeyEvent},
+    props::{Color, Table as TableContent, TableBuilder, TextModifiers, TextSpan},
+    Component, Event, MockComponent,
+};
+
+use crate::ui::{event::UserEvent, notification::Notification, AppMsg};
+
+/// Tells how long ago something happened, in the largest fitting unit
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else {
+        format!("{}h ago", secs / 3600)
+    }
+}
+
+#[derive(MockComponent)]
+pub struct NotificationsWindow {
+    component: Table,
+}
+
+impl NotificationsWindow {
+    pub fn new(notifications: &[Notification]) -> Self {
+        let mut component = Table::default()
+            .highlighted_color(Color::LightYellow)
+            .scroll(true)
+            .title("Notifications", tuirealm::props::Alignment::Left)
+            .headers(&["When", "Severity", "Message"])
+            .highlighted_str("➤ ")
+            .row_height(1)
+            .widths(&[12, 12, 76])
+            .modifiers(TextModifiers::BOLD | TextModifiers::UNDERLINED);
+
+        if !notifications.is_empty() {
+            component = component.table(Self::table(notifications));
+        }
+
+        Self { component }
+    }
+
+    /// Builds the table listing `notifications`, one per row. It can be
+    /// used as `Attribute::Content` to refresh the window.
+    pub fn table(notifications: &[Notification]) -> TableContent {
+        if notifications.is_empty() {
+            return TableContent::new();
+        }
+
+        let mut builder = TableBuilder::default();
+        for (index, notification) in notifications.iter().enumerate() {
+            builder.add_col(TextSpan::new(format_age(notification.time.elapsed())).italic());
+            builder.add_col(
+                TextSpan::new(notification.severity.to_string())
+                    .fg(notification.severity.color())
+                    .italic(),
+            );
+            builder.add_col(TextSpan::new(&notification.message).italic());
+            if index < notifications.len() - 1 {
+                builder.add_row();
+            }
+        }
+        builder.build()
+    }
+
+    pub fn boxed(self) -> Box<Self> {
+        Box::new(self)
+    }
+}
+
+impl Component<AppMsg, UserEvent> for NotificationsWindow {
+    fn on(&mut self, ev: tuirealm::Event<UserEvent>) -> Option<AppMsg> {
+        let cmd = match ev {
+            Event::Keyboard(KeyEvent {
+                code: Key::Down, ..
+            }) => Cmd::Move(Direction::Down),
+            Event::Keyboard(KeyEvent { code: Key::Up, .. }) => Cmd::Move(Direction::Up),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageDown,
+                ..
+            }) => Cmd::Scroll(Direction::Down),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageUp, ..
+            }) => Cmd::Scroll(Direction::Up),
+            Event::Keyboard(KeyEvent {
+                code: Key::Home, ..
+            }) => Cmd::GoTo(Position::Begin),
+            Event::Keyboard(KeyEvent { code: Key::End, .. }) => Cmd::GoTo(Position::End),
+            Event::Keyboard(KeyEvent { code: Key::Tab, .. }) => return Some(AppMsg::GoNextItem),
+            _ => Cmd::None,
+        };
+
+        let _ = self.perform(cmd);
+        Some(AppMsg::None)
+    }
+}
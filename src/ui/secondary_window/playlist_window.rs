@@ -1,11 +1,11 @@
-use phosphorus_core::playlist_manager::Playlist;
+use phosphorus_core::{playlist_manager::Playlist, song::Song};
 
 use tui_realm_stdlib::Table;
 use tuirealm::{
     command::{Cmd, Direction, Position},
     event::{Key, KeyEvent},
     props::{Color, TableBuilder, TextModifiers, TextSpan},
-    Component, Event, MockComponent,
+    Component, Event, MockComponent, State, StateValue,
 };
 
 use crate::ui::{event::UserEvent, AppMsg};
@@ -16,10 +16,13 @@ const UNKNOWN_DURATION: &'static str = " - ";
 #[derive(MockComponent)]
 pub struct PlaylistWindow {
     component: Table,
+    /// Index of the playlist being shown
+    index: usize,
+    songs: Vec<Song>,
 }
 
 impl PlaylistWindow {
-    pub fn new(playlist: &Playlist) -> Self {
+    pub fn new(playlist: &Playlist, index: usize) -> Self {
         let songs = playlist.songs();
 
         let mut builder = TableBuilder::default();
@@ -53,7 +56,11 @@ impl PlaylistWindow {
             component = component.table(builder.build());
         }
 
-        Self { component }
+        Self {
+            component,
+            index,
+            songs: songs.to_vec(),
+        }
     }
 
     pub fn boxed(self) -> Box<Self> {
@@ -83,9 +90,14 @@ impl Component<AppMsg, UserEvent> for PlaylistWindow {
             Event::Keyboard(KeyEvent {
                 code: Key::Enter, ..
             }) => {
-                return Some(AppMsg::PlayFromPlaylist(
-                    self.state().unwrap_one().unwrap_usize(),
-                ))
+                if let State::One(StateValue::Usize(index)) = self.state() {
+                    return Some(AppMsg::PlayFromPlaylist {
+                        playlist: self.index,
+                        songs: self.songs.clone(),
+                        index,
+                    });
+                }
+                Cmd::None
             }
             _ => Cmd::None,
         };
@@ -0,0 +1,90 @@
+use tui_realm_stdlib::Table;
+use tuirealm::{
+    command::{Cmd, Direction, Position},
+    event::{Key, KeyEvent},
+    props::{Color, Table as TableContent, TableBuilder, TextModifiers, TextSpan},
+    Component, Event, MockComponent,
+};
+
+use crate::ui::{event::UserEvent, AppMsg};
+
+#[derive(MockComponent)]
+pub struct PluginsWindow {
+    component: Table,
+}
+
+impl PluginsWindow {
+    /// Lists the registered `plugins`, marking the `active` one
+    pub fn new(plugins: &[String], active: Option<&str>) -> Self {
+        let mut component = Table::default()
+            .highlighted_color(Color::LightYellow)
+            .scroll(true)
+            .title("Plugins", tuirealm::props::Alignment::Left)
+            .headers(&["#", "Name", "Default"])
+            .highlighted_str("➤ ")
+            .row_height(1)
+            .widths(&[5, 75, 20])
+            .modifiers(TextModifiers::BOLD | TextModifiers::UNDERLINED);
+
+        if !plugins.is_empty() {
+            component = component.table(Self::table(plugins, active));
+        }
+
+        Self { component }
+    }
+
+    /// Builds the table listing `plugins`, one per row. It can be used
+    /// as `Attribute::Content` to refresh the window.
+    pub fn table(plugins: &[String], active: Option<&str>) -> TableContent {
+        if plugins.is_empty() {
+            return TableContent::new();
+        }
+
+        let mut builder = TableBuilder::default();
+        for (index, plugin) in plugins.iter().enumerate() {
+            let marker = if Some(plugin.as_str()) == active {
+                "✓"
+            } else {
+                ""
+            };
+            builder.add_col(TextSpan::new(index.to_string()).italic());
+            builder.add_col(TextSpan::new(plugin).italic());
+            builder.add_col(TextSpan::new(marker).fg(Color::LightGreen));
+            if index < plugins.len() - 1 {
+                builder.add_row();
+            }
+        }
+        builder.build()
+    }
+
+    pub fn boxed(self) -> Box<Self> {
+        Box::new(self)
+    }
+}
+
+impl Component<AppMsg, UserEvent> for PluginsWindow {
+    fn on(&mut self, ev: tuirealm::Event<UserEvent>) -> Option<AppMsg> {
+        let cmd = match ev {
+            Event::Keyboard(KeyEvent {
+                code: Key::Down, ..
+            }) => Cmd::Move(Direction::Down),
+            Event::Keyboard(KeyEvent { code: Key::Up, .. }) => Cmd::Move(Direction::Up),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageDown,
+                ..
+            }) => Cmd::Scroll(Direction::Down),
+            Event::Keyboard(KeyEvent {
+                code: Key::PageUp, ..
+            }) => Cmd::Scroll(Direction::Up),
+            Event::Keyboard(KeyEvent {
+                code: Key::Home, ..
+            }) => Cmd::GoTo(Position::Begin),
+            Event::Keyboard(KeyEvent { code: Key::End, .. }) => Cmd::GoTo(Position::End),
+            Event::Keyboard(KeyEvent { code: Key::Tab, .. }) => return Some(AppMsg::GoNextItem),
+            _ => Cmd::None,
+        };
+
+        let _ = self.perform(cmd);
+        Some(AppMsg::None)
+    }
+}
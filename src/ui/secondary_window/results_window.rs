@@ -1,51 +1,211 @@
-use phosphorus_core::plugin_manager::query::QueryResult;
 use tui_realm_stdlib::Table;
 use tuirealm::{
-    props::{Color, TableBuilder, TextModifiers, TextSpan},
+    props::{Alignment, Color, Table as TableContent, TableBuilder, TextModifiers, TextSpan},
     Component, MockComponent,
 };
 
-use crate::ui::{event::UserEvent, AppMsg};
+use crate::ui::{
+    event::UserEvent, plugin_registry::SearchResult, search_query::SearchQuery, AppMsg,
+};
 
-#[derive(MockComponent)]
-pub struct ResultsWindow {
-    component: Table,
+const TITLE: &'static str = "Search results";
+
+/// Column the results are sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortColumn {
+    /// Order in which plugins sent the results
+    #[default]
+    Index,
+    Name,
+    Artist,
+    Duration,
+    Source,
 }
 
-impl ResultsWindow {
-    pub fn new(result: &QueryResult) -> Self {
-        let data = result.data();
+impl SortColumn {
+    /// The column on the right, or the first one after the last
+    fn next(self) -> Self {
+        match self {
+            SortColumn::Index => SortColumn::Name,
+            SortColumn::Name => SortColumn::Artist,
+            SortColumn::Artist => SortColumn::Duration,
+            SortColumn::Duration => SortColumn::Source,
+            SortColumn::Source => SortColumn::Index,
+        }
+    }
+}
 
-        let mut builder = TableBuilder::default();
-        if data.len() > 0 {
-            for (index, item) in data.iter().enumerate() {
-                builder.add_col(TextSpan::new(index.to_string()).italic());
-                builder.add_col(TextSpan::new(item.track_name()).italic());
-                builder.add_col(TextSpan::new(item.artist_name()).italic());
-                builder.add_col(TextSpan::new(item.duration_str()).italic());
-                if index < data.len() - 1 {
-                    builder.add_row();
-                }
+impl std::fmt::Display for SortColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortColumn::Index => write!(f, "#"),
+            SortColumn::Name => write!(f, "Name"),
+            SortColumn::Artist => write!(f, "Artist"),
+            SortColumn::Duration => write!(f, "Duration"),
+            SortColumn::Source => write!(f, "Source"),
+        }
+    }
+}
+
+/// Tells whether every word of `words` appears in `text`, ignoring case
+fn contains_all(text: &str, words: &str) -> bool {
+    words
+        .to_lowercase()
+        .split_whitespace()
+        .all(|word| text.contains(word))
+}
+
+/// How the results are filtered and sorted in the window. The filter
+/// is written as a query, so `dur:<5m` leaves out the longer results.
+/// A malformed filter is applied as plain text.
+#[derive(Debug, Clone, Default)]
+pub struct ResultsView {
+    filter: String,
+    /// Tells whether the filter is being typed
+    pub editing: bool,
+    sort: SortColumn,
+    descending: bool,
+}
+
+impl ResultsView {
+    pub fn push(&mut self, ch: char) {
+        self.filter.push(ch);
+    }
+
+    pub fn pop(&mut self) {
+        self.filter.pop();
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.editing = false;
+    }
+
+    /// Sorts by the next column, in ascending order
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.descending = false;
+    }
+
+    /// Switches between ascending and descending order
+    pub fn reverse(&mut self) {
+        self.descending = !self.descending;
+    }
+
+    /// Tells whether `result` passes the filter
+    fn admits(filter: &SearchQuery, result: &SearchResult) -> bool {
+        let name = result.track_name().to_lowercase();
+        let artist = result.artist_name().to_lowercase();
+        filter
+            .title
+            .as_deref()
+            .is_none_or(|title| contains_all(&name, title))
+            && filter
+                .artist
+                .as_deref()
+                .is_none_or(|words| contains_all(&artist, words))
+            && filter
+                .free
+                .to_lowercase()
+                .split_whitespace()
+                .all(|word| name.contains(word) || artist.contains(word))
+            && filter.admits(result.duration())
+    }
+
+    /// Returns the indexes in `data` of the results shown, in the order shown
+    pub fn apply(&self, data: &[SearchResult]) -> Vec<usize> {
+        let filter = SearchQuery::parse_filter(&self.filter)
+            .unwrap_or_else(|_| SearchQuery::raw(&self.filter));
+        let mut shown: Vec<usize> = (0..data.len())
+            .filter(|index| Self::admits(&filter, &data[*index]))
+            .collect();
+
+        match self.sort {
+            SortColumn::Index => (),
+            SortColumn::Name => shown.sort_by_key(|index| data[*index].track_name().to_lowercase()),
+            SortColumn::Artist => {
+                shown.sort_by_key(|index| data[*index].artist_name().to_lowercase())
+            }
+            SortColumn::Duration => shown.sort_by_key(|index| data[*index].duration()),
+            SortColumn::Source => shown.sort_by_key(|index| data[*index].plugin.clone()),
+        }
+        if self.descending {
+            shown.reverse();
+        }
+        shown
+    }
+
+    /// Returns the result shown at `row`
+    pub fn get<'a>(&self, data: &'a [SearchResult], row: usize) -> Option<&'a SearchResult> {
+        self.apply(data).get(row).map(|index| &data[*index])
+    }
+
+    /// Title of the window, telling the filter and the sort in use
+    pub fn title(&self) -> String {
+        let mut title = String::from(TITLE);
+        if self.editing || !self.filter.is_empty() {
+            let cursor = if self.editing { "_" } else { "" };
+            title.push_str(&format!(" | filter: {}{}", self.filter, cursor));
+            if let Err(error) = SearchQuery::parse_filter(&self.filter) {
+                title.push_str(&format!(" ({}, filtering as plain text)", error));
             }
         }
+        if self.sort != SortColumn::Index || self.descending {
+            let order = if self.descending { "↓" } else { "↑" };
+            title.push_str(&format!(" | sorted by {} {}", self.sort, order));
+        }
+        title
+    }
+}
+
+#[derive(MockComponent)]
+pub struct ResultsWindow {
+    component: Table,
+}
 
+impl ResultsWindow {
+    pub fn new(data: &[SearchResult], view: &ResultsView) -> Self {
         let mut component = Table::default()
             .highlighted_color(Color::LightYellow)
             .scroll(true)
-            .title("Search results", tuirealm::props::Alignment::Left)
-            .headers(&["#", "Name", "Artist", "Duration"])
+            .title(view.title(), Alignment::Left)
+            .headers(&["#", "Name", "Artist", "Duration", "Source"])
             .highlighted_str("➤ ")
             .row_height(1)
-            .widths(&[5, 50, 30, 15])
+            .widths(&[5, 40, 25, 15, 15])
             .modifiers(TextModifiers::BOLD | TextModifiers::UNDERLINED);
 
-        if data.len() > 0 {
-            component = component.table(builder.build());
+        if !data.is_empty() {
+            component = component.table(Self::table(data, view));
         }
 
         Self { component }
     }
 
+    /// Builds the table listing `data` as filtered and sorted by `view`, one
+    /// result per row. It can be used as `Attribute::Content` to refresh the
+    /// window. Results keep the number they have in `data`.
+    pub fn table(data: &[SearchResult], view: &ResultsView) -> TableContent {
+        let shown = view.apply(data);
+        if shown.is_empty() {
+            return TableContent::new();
+        }
+
+        let mut builder = TableBuilder::default();
+        for (row, index) in shown.iter().enumerate() {
+            let result = &data[*index];
+            builder.add_col(TextSpan::new(index.to_string()).italic());
+            builder.add_col(TextSpan::new(result.track_name()).italic());
+            builder.add_col(TextSpan::new(result.artist_name()).italic());
+            builder.add_col(TextSpan::new(result.duration_str()).italic());
+            builder.add_col(TextSpan::new(&result.plugin).italic());
+            if row < shown.len() - 1 {
+                builder.add_row();
+            }
+        }
+        builder.build()
+    }
+
     pub fn boxed(self) -> Box<Self> {
         Box::new(self)
     }
@@ -56,3 +216,97 @@ impl Component<AppMsg, UserEvent> for ResultsWindow {
         Some(AppMsg::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::ui::plugin_registry::{ResultData, Track};
+
+    fn result(plugin: &str, name: &str, artist: &str, secs: u64) -> SearchResult {
+        SearchResult {
+            plugin: plugin.to_string(),
+            data: ResultData::Remote(Track {
+                name: name.to_string(),
+                artist: artist.to_string(),
+                duration: Duration::from_secs(secs),
+                url: format!("https://example.com/{}", name),
+            }),
+        }
+    }
+
+    fn results() -> Vec<SearchResult> {
+        vec![
+            result("YouTube", "Around the World", "Daft Punk", 429),
+            result("Local", "One More Time", "Daft Punk", 320),
+            result("YouTube", "Levitating", "Dua Lipa", 203),
+            result("Other", "around", "Someone", 150),
+        ]
+    }
+
+    fn view(filter: &str) -> ResultsView {
+        let mut view = ResultsView::default();
+        filter.chars().for_each(|ch| view.push(ch));
+        view
+    }
+
+    #[test]
+    fn shows_everything_in_order_without_a_filter() {
+        assert_eq!(ResultsView::default().apply(&results()), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn filters_by_fields_words_and_duration() {
+        let data = results();
+        assert_eq!(view("daft").apply(&data), vec![0, 1]);
+        assert_eq!(view("AROUND").apply(&data), vec![0, 3]);
+        assert_eq!(view("artist:daft title:time").apply(&data), vec![1]);
+        assert_eq!(view(r#"artist:"dua lipa""#).apply(&data), vec![2]);
+        assert_eq!(view("dur:<6m dur:>3m").apply(&data), vec![1, 2]);
+        assert_eq!(view("around dur:>5m").apply(&data), vec![0]);
+    }
+
+    #[test]
+    fn applies_malformed_filters_as_plain_text() {
+        let data = results();
+        assert!(view("album:discovery").apply(&data).is_empty());
+        assert!(view("dur:soon").apply(&data).is_empty());
+        assert!(view("album:discovery")
+            .title()
+            .contains("filtering as plain text"));
+    }
+
+    #[test]
+    fn sorts_by_the_chosen_column() {
+        let data = results();
+        let mut view = ResultsView::default();
+        view.cycle_sort();
+        assert_eq!(view.sort, SortColumn::Name);
+        assert_eq!(view.apply(&data), vec![3, 0, 2, 1]);
+
+        view.cycle_sort();
+        view.cycle_sort();
+        assert_eq!(view.sort, SortColumn::Duration);
+        assert_eq!(view.apply(&data), vec![3, 2, 1, 0]);
+        view.reverse();
+        assert_eq!(view.apply(&data), vec![0, 1, 2, 3]);
+
+        // Sorting again starts from ascending order
+        view.cycle_sort();
+        assert_eq!(view.sort, SortColumn::Source);
+        assert_eq!(view.apply(&data), vec![1, 3, 0, 2]);
+        assert_eq!(view.get(&data, 1), Some(&data[3]));
+        assert_eq!(view.get(&data, 4), None);
+    }
+
+    #[test]
+    fn sorts_only_the_results_passing_the_filter() {
+        let data = results();
+        let mut view = view("daft");
+        view.cycle_sort();
+        view.reverse();
+        assert_eq!(view.apply(&data), vec![1, 0]);
+        assert_eq!(view.get(&data, 0), Some(&data[1]));
+    }
+}
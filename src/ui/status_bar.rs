@@ -8,10 +8,17 @@ use tuirealm::{
     AttrValue, Attribute, Component, Event, MockComponent,
 };
 
-use super::{event::UserEvent, AppMsg};
+use super::{
+    event::UserEvent,
+    notification::{Notification, Severity},
+    AppMsg,
+};
 
 const LEFT_LABEL: usize = 0;
-const RIGHT_LABEL: usize = 1;
+const NOTIFICATION_LABEL: usize = 1;
+const RIGHT_LABEL: usize = 2;
+
+const BACKGROUND: Color = Color::LightGreen;
 
 /// Defines how many times ESC has to be
 /// pressed before the application closes
@@ -23,11 +30,19 @@ const QUIT_MSG: &'static str = "Press ESC again to quit";
 const HELP_MSG: &'static str = "Press ESC to close help window";
 /// Message shown when a playlist view is opened and visible
 const PLAYLIST_MSG: &'static str = "Press ESC to close playlist window";
+/// Message shown when the downloads window is opened and visible
+const DOWNLOADS_MSG: &'static str = "Press ESC to close downloads window";
+/// Message shown when the notifications window is opened and visible
+const NOTIFICATIONS_MSG: &'static str = "Press ESC to close notifications window";
+/// Message shown when the plugins window is opened and visible
+const PLUGINS_MSG: &'static str = "Press ESC to close plugins window";
 
 const QUERY_SENT_MSG_1: &'static str = "Fetching results.  ";
 const QUERY_SENT_MSG_2: &'static str = "Fetching results.. ";
 const QUERY_SENT_MSG_3: &'static str = "Fetching results...";
 const QUERY_SOLVED_MSG: &'static str = "Results fetched in";
+const QUERY_TIMED_OUT_MSG: &'static str = "Search timed out after";
+const QUERY_CANCELLED_MSG: &'static str = "Search cancelled";
 
 #[derive(MockComponent)]
 pub struct StatusBar {
@@ -35,19 +50,24 @@ pub struct StatusBar {
     is_secondary_window_active: bool,
     esc_count: u16,
     timer: Option<std::time::Instant>,
+    /// Name and artist of the song being reproduced
+    current_song: Option<String>,
+    /// Notification being shown, until it expires
+    notification: Option<Notification>,
 }
 
 impl StatusBar {
     pub fn new() -> Self {
         let children: Vec<Box<dyn MockComponent>> = vec![
             Box::new(Label::default().alignment(Alignment::Left).text(STD_MSG)),
+            Box::new(Label::default().alignment(Alignment::Center)),
             Box::new(Label::default().alignment(Alignment::Right).text("Welcome")),
         ];
 
         StatusBar {
             component: Container::default()
                 .children(children)
-                .background(Color::LightGreen)
+                .background(BACKGROUND)
                 .foreground(Color::Black)
                 .borders(Borders::default().sides(BorderSides::empty()))
                 .layout(
@@ -55,8 +75,9 @@ impl StatusBar {
                         .direction(Direction::Horizontal)
                         .constraints(
                             [
-                                Constraint::Percentage(50), // Left label
-                                Constraint::Percentage(50), // Right label
+                                Constraint::Percentage(30), // Left label
+                                Constraint::Percentage(40), // Notification
+                                Constraint::Percentage(30), // Right label
                             ]
                             .as_ref(),
                         ),
@@ -64,7 +85,35 @@ impl StatusBar {
             is_secondary_window_active: false,
             esc_count: 0,
             timer: None,
+            current_song: None,
+            notification: None,
+        }
+    }
+
+    /// Shows `notification`, or clears the notification label if `None`
+    fn show_notification(&mut self, notification: Option<Notification>) {
+        let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
+        let child: &mut Box<dyn MockComponent> = children.get_mut(NOTIFICATION_LABEL).unwrap();
+        match &notification {
+            Some(notification) => {
+                child.attr(
+                    Attribute::Text,
+                    AttrValue::String(format!(
+                        "{}: {}",
+                        notification.severity, notification.message
+                    )),
+                );
+                child.attr(
+                    Attribute::Background,
+                    AttrValue::Color(notification.severity.color()),
+                );
+            }
+            None => {
+                child.attr(Attribute::Text, AttrValue::String(String::new()));
+                child.attr(Attribute::Background, AttrValue::Color(BACKGROUND));
+            }
         }
+        self.notification = notification;
     }
 
     pub fn boxed(self) -> Box<Self> {
@@ -74,6 +123,15 @@ impl StatusBar {
 
 impl Component<AppMsg, UserEvent> for StatusBar {
     fn on(&mut self, ev: tuirealm::Event<UserEvent>) -> Option<AppMsg> {
+        // Expired notifications are removed
+        let expired = self
+            .notification
+            .as_ref()
+            .is_some_and(|notification| notification.is_expired());
+        if expired {
+            self.show_notification(None);
+        }
+
         let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
 
         let event = match ev {
@@ -94,8 +152,12 @@ impl Component<AppMsg, UserEvent> for StatusBar {
                         }
                         _ => (),
                     }
+                    return Some(AppMsg::None);
                 }
 
+                if expired {
+                    return Some(AppMsg::None);
+                }
                 return None;
             }
             Event::Keyboard(KeyEvent { code: Key::Esc, .. }) => {
@@ -107,6 +169,11 @@ impl Component<AppMsg, UserEvent> for StatusBar {
                     return Some(AppMsg::LoseFocus);
                 }
 
+                // ESC abandons the search being served, if any
+                if self.timer.is_some() {
+                    return Some(AppMsg::CancelQuery);
+                }
+
                 self.esc_count += 1;
                 if self.esc_count == MAX_ESC_TOLERANCE {
                     return Some(AppMsg::Quit);
@@ -136,12 +203,29 @@ impl Component<AppMsg, UserEvent> for StatusBar {
                 child.attr(Attribute::Text, AttrValue::String(PLAYLIST_MSG.into()));
                 self.is_secondary_window_active = true;
             }
+            UserEvent::DownloadsViewOpened => {
+                let child: &mut Box<dyn MockComponent> = children.get_mut(LEFT_LABEL).unwrap();
+                child.attr(Attribute::Text, AttrValue::String(DOWNLOADS_MSG.into()));
+                self.is_secondary_window_active = true;
+            }
+            UserEvent::NotificationsViewOpened => {
+                let child: &mut Box<dyn MockComponent> = children.get_mut(LEFT_LABEL).unwrap();
+                child.attr(Attribute::Text, AttrValue::String(NOTIFICATIONS_MSG.into()));
+                self.is_secondary_window_active = true;
+            }
+            UserEvent::PluginsViewOpened => {
+                let child: &mut Box<dyn MockComponent> = children.get_mut(LEFT_LABEL).unwrap();
+                child.attr(Attribute::Text, AttrValue::String(PLUGINS_MSG.into()));
+                self.is_secondary_window_active = true;
+            }
+            UserEvent::Notified(notification) => self.show_notification(Some(notification)),
             UserEvent::QuerySent => {
                 let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
                 self.timer = Some(Instant::now());
                 child.attr(Attribute::Text, AttrValue::String(QUERY_SENT_MSG_1.into()));
             }
-            UserEvent::QueryResult(_) => {
+            // Results may keep coming until every plugin has answered
+            UserEvent::QueryCompleted => {
                 let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
                 if let Some(instant) = self.timer {
                     child.attr(
@@ -156,16 +240,81 @@ impl Component<AppMsg, UserEvent> for StatusBar {
                 }
             }
             UserEvent::PlaySong(song) => {
+                let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
+                let current_song = format!(
+                    "{} by {}",
+                    song.details().name(),
+                    song.details().artist().unwrap_or("Uknown")
+                );
+                child.attr(
+                    Attribute::Text,
+                    AttrValue::String(format!("Playing {}", current_song)),
+                );
+                self.current_song = Some(current_song);
+            }
+            UserEvent::PlaybackPaused => {
+                if let Some(current_song) = &self.current_song {
+                    let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
+                    child.attr(
+                        Attribute::Text,
+                        AttrValue::String(format!("Paused {}", current_song)),
+                    );
+                }
+            }
+            UserEvent::PlaybackResumed => {
+                if let Some(current_song) = &self.current_song {
+                    let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
+                    child.attr(
+                        Attribute::Text,
+                        AttrValue::String(format!("Playing {}", current_song)),
+                    );
+                }
+            }
+            UserEvent::PlaybackStopped | UserEvent::TrackEnded => {
+                let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
+                child.attr(Attribute::Text, AttrValue::String(String::new()));
+                self.current_song = None;
+            }
+            UserEvent::QueryTimedOut(secs) => {
                 let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
                 child.attr(
                     Attribute::Text,
-                    AttrValue::String(format!(
-                        "Playing {} by {}",
-                        song.details().name(),
-                        song.details().artist().unwrap_or("Uknown")
-                    )),
+                    AttrValue::String(format!("{} {}s", QUERY_TIMED_OUT_MSG, secs)),
                 );
+                self.timer = None;
+            }
+            UserEvent::QueryCancelled => {
+                let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
+                child.attr(
+                    Attribute::Text,
+                    AttrValue::String(QUERY_CANCELLED_MSG.into()),
+                );
+                self.timer = None;
+            }
+            UserEvent::QueryError(error) => {
+                // The search is over, even if it has failed
+                let child: &mut Box<dyn MockComponent> = children.get_mut(RIGHT_LABEL).unwrap();
+                child.attr(Attribute::Text, AttrValue::String(String::new()));
+                self.timer = None;
+                return Some(AppMsg::SearchFailed(error));
+            }
+            // Errors are reported by the model, the status bar only forwards them
+            UserEvent::PlayError(error) => return Some(AppMsg::PlaybackFailed(error)),
+            UserEvent::DownloadError(error) => return Some(AppMsg::DownloadFailed(error, false)),
+            UserEvent::DownloadRetrying(error) => return Some(AppMsg::DownloadFailed(error, true)),
+            UserEvent::DefaultPluginChanged(name) => {
+                return Some(AppMsg::Notify(
+                    Severity::Info,
+                    format!("Searching with {}", name),
+                ));
+            }
+            UserEvent::NoMoreResults => {
+                return Some(AppMsg::Notify(
+                    Severity::Info,
+                    String::from("No more results"),
+                ));
             }
+            UserEvent::PluginError(error) => return Some(AppMsg::PluginFailed(error)),
             _ => (),
         }
         Some(AppMsg::None)
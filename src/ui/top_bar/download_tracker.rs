@@ -1,34 +1,28 @@
-use std::sync::mpsc::{Receiver, Sender};
-
 use tui_realm_stdlib::{Container, Label, Phantom};
 use tuirealm::{
     command::{Cmd, CmdResult},
     props::{Alignment, BorderSides, Borders, Color, Layout, TextModifiers},
     tui::layout::{Constraint, Direction},
-    MockComponent, State,
+    AttrValue, Attribute, MockComponent, State,
 };
 
-const DOWNLOAD_FOLLOWER: usize = 1;
+use crate::ui::download_manager::{DownloadState, Downloads};
 
-pub enum TrackInfo {
-    New(String),
-    Started,
-    Progress(f32),
-    Finished,
-}
+const DOWNLOAD_FOLLOWER: usize = 1;
+const DOWNLOAD_COUNTER: usize = 2;
 
+/// Shows the progress of the current download and how many others
+/// are waiting. It reads the state of downloads from `Downloads` on
+/// `Cmd::Change`, so it never waits for the download manager.
 pub(super) struct DownloadTracker {
     component: Container,
-    downloads: Vec<String>,
-    track_info_rx: Receiver<TrackInfo>,
-    download_count_tx: Sender<usize>,
-    current: Option<String>,
+    downloads: Downloads,
+    /// Text of the follower and count shown the last time
+    shown: (String, usize),
 }
 
 impl DownloadTracker {
-    pub fn new(rx: Receiver<TrackInfo>) -> Self {
-        let (tx, internal_rx) = std::sync::mpsc::channel();
-
+    pub fn new(downloads: Downloads) -> Self {
         let children: Vec<Box<dyn MockComponent>> = vec![
             Box::new(Phantom::default()),
             Box::new(
@@ -36,7 +30,7 @@ impl DownloadTracker {
                     .modifiers(TextModifiers::ITALIC)
                     .alignment(Alignment::Right),
             ),
-            DownloadCounter::new(internal_rx).boxed(),
+            DownloadCounter::default().boxed(),
         ];
 
         Self {
@@ -57,16 +51,56 @@ impl DownloadTracker {
                         )
                         .horizontal_margin(1),
                 ),
-            downloads: vec![],
-            track_info_rx: rx,
-            download_count_tx: tx,
-            current: None,
+            downloads,
+            shown: (String::new(), 0),
         }
     }
 
     pub fn boxed(self) -> Box<Self> {
         Box::new(self)
     }
+
+    /// Reads the state of downloads and updates the widgets.
+    /// Returns whether anything has changed.
+    fn refresh(&mut self) -> bool {
+        let entries = self.downloads.entries();
+        let mut running = entries.iter().filter_map(|entry| match entry.state {
+            DownloadState::Running(perc) => Some((&entry.name, perc)),
+            _ => None,
+        });
+        let follower = running
+            .next()
+            .map(|(name, perc)| format_downloading(name, perc))
+            .unwrap_or_default();
+
+        // Everything that is going to be served, except the download being shown
+        let waiting = running.count()
+            + entries
+                .iter()
+                .filter(|entry| {
+                    matches!(
+                        entry.state,
+                        DownloadState::Queued | DownloadState::Retrying(_)
+                    )
+                })
+                .count();
+
+        if self.shown.0 == follower && self.shown.1 == waiting {
+            return false;
+        }
+
+        let children: &mut Vec<Box<dyn MockComponent>> = self.component.children.as_mut();
+        children
+            .get_mut(DOWNLOAD_FOLLOWER)
+            .unwrap()
+            .attr(Attribute::Text, AttrValue::String(follower.clone()));
+        children
+            .get_mut(DOWNLOAD_COUNTER)
+            .unwrap()
+            .attr(Attribute::Text, AttrValue::String(format_count(waiting)));
+        self.shown = (follower, waiting);
+        true
+    }
 }
 
 impl MockComponent for DownloadTracker {
@@ -88,54 +122,26 @@ impl MockComponent for DownloadTracker {
 
     fn perform(&mut self, cmd: tuirealm::command::Cmd) -> tuirealm::command::CmdResult {
         match cmd {
-            tuirealm::command::Cmd::Change => {
-                match self.track_info_rx.recv().unwrap() {
-                    TrackInfo::New(song_name) => {
-                        self.downloads.push(song_name);
-                        let _ = self.download_count_tx.send(self.downloads.len());
-                        self.component.perform(Cmd::Change);
-                    }
-                    TrackInfo::Started => {
-                        let song_name = self.downloads.pop().unwrap();
-                        self.component.perform(Cmd::Change);
-                        let follower = self.component.children.get_mut(DOWNLOAD_FOLLOWER).unwrap();
-                        follower.attr(
-                            tuirealm::Attribute::Text,
-                            tuirealm::AttrValue::String(format_downloading(&song_name, 0.0)),
-                        );
-                        self.current = Some(song_name);
-                    }
-                    TrackInfo::Progress(perc) => {
-                        let song_name = self.current.as_ref().unwrap();
-                        let follower = self.component.children.get_mut(DOWNLOAD_FOLLOWER).unwrap();
-                        follower.attr(
-                            tuirealm::Attribute::Text,
-                            tuirealm::AttrValue::String(format_downloading(&song_name, perc)),
-                        );
-                    }
-                    TrackInfo::Finished => {
-                        self.current = None;
-                        let follower = self.component.children.get_mut(DOWNLOAD_FOLLOWER).unwrap();
-                        follower.attr(
-                            tuirealm::Attribute::Text,
-                            tuirealm::AttrValue::String(String::new()),
-                        );
-                    }
-                };
-                CmdResult::None
+            Cmd::Change => {
+                if self.refresh() {
+                    CmdResult::Changed(State::None)
+                } else {
+                    CmdResult::None
+                }
             }
             _ => self.component.perform(cmd),
         }
     }
 }
 
+const COUNT_LABEL: usize = 1;
+
 struct DownloadCounter {
     component: Container,
-    rx: Receiver<usize>,
 }
 
-impl DownloadCounter {
-    pub fn new(rx: Receiver<usize>) -> Self {
+impl Default for DownloadCounter {
+    fn default() -> Self {
         let children: Vec<Box<dyn MockComponent>> = vec![
             Box::new(Phantom::default()),
             Box::new(
@@ -158,10 +164,11 @@ impl DownloadCounter {
                             [Constraint::Min(1), Constraint::Fill(1), Constraint::Min(1)].as_ref(),
                         ),
                 ),
-            rx,
         }
     }
+}
 
+impl DownloadCounter {
     pub fn boxed(self) -> Box<Self> {
         Box::new(self)
     }
@@ -177,7 +184,16 @@ impl MockComponent for DownloadCounter {
     }
 
     fn attr(&mut self, attr: tuirealm::Attribute, value: tuirealm::AttrValue) {
-        self.component.attr(attr, value);
+        // The text is the count, which is shown by the label
+        match attr {
+            Attribute::Text => self
+                .component
+                .children
+                .get_mut(COUNT_LABEL)
+                .unwrap()
+                .attr(attr, value),
+            _ => self.component.attr(attr, value),
+        }
     }
 
     fn state(&self) -> State {
@@ -185,31 +201,18 @@ impl MockComponent for DownloadCounter {
     }
 
     fn perform(&mut self, cmd: tuirealm::command::Cmd) -> tuirealm::command::CmdResult {
-        match cmd {
-            tuirealm::command::Cmd::Change => {
-                let count = match self.rx.recv() {
-                    Ok(count) => count,
-                    Err(err) => {
-                        eprintln!("ERROR: {err}"); std::process::exit(1);},
-                };
-                self.component.attr(
-                    tuirealm::Attribute::Text,
-                    tuirealm::AttrValue::String(format_count(count)),
-                );
-                tuirealm::command::CmdResult::None
-            }
-            _ => self.component.perform(cmd),
-        }
+        self.component.perform(cmd)
     }
 }
 
 const MAX_NAME_LENGTH: usize = 8;
 const DOWNLOAD_STR_LENGTH: usize = MAX_NAME_LENGTH + 12;
 fn format_downloading(name: &str, percentage: f32) -> String {
-    if name.len() > MAX_NAME_LENGTH + 3 {
+    // Names are cut by characters, as a byte may fall inside one
+    if name.chars().count() > MAX_NAME_LENGTH + 3 {
         format!(
             "\n{:<MAX_NAME_LENGTH$}...: {:02.2}% ",
-            &name[0..MAX_NAME_LENGTH],
+            name.chars().take(MAX_NAME_LENGTH).collect::<String>(),
             percentage
         )
     } else {
@@ -1,9 +1,7 @@
-use std::sync::mpsc::Sender;
-
-use download_tracker::{DownloadTracker, TrackInfo};
+use download_tracker::DownloadTracker;
 use tui_realm_stdlib::{Container, Phantom};
 use tuirealm::{
-    command::{Cmd, Position},
+    command::{Cmd, CmdResult, Position},
     event::{Key, KeyEvent, KeyModifiers},
     props::{BorderSides, Borders, Color, Layout},
     tui::layout::{Constraint, Direction},
@@ -13,9 +11,12 @@ use tuirealm::{
 mod download_tracker;
 mod search_bar;
 
-use self::search_bar::SearchBar;
+use self::search_bar::{query, set_query, show_hint, SearchBar};
 
-use super::{event::UserEvent, AppMsg};
+use super::{
+    download_manager::Downloads, event::UserEvent, local_library::Library,
+    search_history::SearchHistory, AppMsg,
+};
 
 const SEARCH_BAR: usize = 1;
 const DOWNLOAD_TRACKER: usize = 2;
@@ -23,22 +24,25 @@ const DOWNLOAD_TRACKER: usize = 2;
 #[derive(MockComponent)]
 pub struct TopBar {
     component: Container,
-    tx: Sender<TrackInfo>,
+    /// Queries sent so far, recalled with Up and Down
+    history: SearchHistory,
+    /// Songs whose names and artists complete the query typed
+    library: Library,
+    /// How many queries back the one shown has been recalled from, if any
+    recall: Option<usize>,
+    /// Query typed before starting to recall, restored when going past
+    /// the most recent query
+    draft: String,
+    /// Completion of the query typed, accepted with End
+    suggestion: Option<String>,
 }
 
 impl TopBar {
-    pub fn boxed(self) -> Box<Self> {
-        Box::new(self)
-    }
-}
-
-impl Default for TopBar {
-    fn default() -> Self {
-        let (tx, rx) = std::sync::mpsc::channel();
+    pub fn new(downloads: Downloads, history: SearchHistory, library: Library) -> Self {
         let children: Vec<Box<dyn MockComponent>> = vec![
             Box::new(Phantom::default()),
             SearchBar::default().boxed(),
-            DownloadTracker::new(rx).boxed(),
+            DownloadTracker::new(downloads).boxed(),
         ];
 
         Self {
@@ -58,9 +62,17 @@ impl Default for TopBar {
                             .as_ref(),
                         ),
                 ),
-            tx,
+            history,
+            library,
+            recall: None,
+            draft: String::new(),
+            suggestion: None,
         }
     }
+
+    pub fn boxed(self) -> Box<Self> {
+        Box::new(self)
+    }
 }
 
 impl Component<AppMsg, UserEvent> for TopBar {
@@ -78,8 +90,57 @@ impl Component<AppMsg, UserEvent> for TopBar {
                 code: Key::Home, ..
             }) => (SEARCH_BAR, Cmd::GoTo(Position::Begin)),
             Event::Keyboard(KeyEvent { code: Key::End, .. }) => {
-                (SEARCH_BAR, Cmd::GoTo(Position::End))
+                // Accepts the completion, if any, leaving the cursor at its end
+                let search_bar = children.get_mut(SEARCH_BAR).unwrap();
+                match self.suggestion.take() {
+                    Some(suggestion) => {
+                        set_query(search_bar, suggestion);
+                        (SEARCH_BAR, Cmd::None)
+                    }
+                    None => (SEARCH_BAR, Cmd::GoTo(Position::End)),
+                }
+            }
+            // Recalls the previous query in the history
+            Event::Keyboard(KeyEvent {
+                code: Key::Up,
+                modifiers: KeyModifiers::NONE,
+            }) => {
+                let search_bar = children.get_mut(SEARCH_BAR).unwrap();
+                let back = self.recall.map_or(0, |back| back + 1);
+                if let Some(recalled) = self.history.recall(back) {
+                    if self.recall.is_none() {
+                        self.draft = query(search_bar.as_ref());
+                    }
+                    self.recall = Some(back);
+                    set_query(search_bar, recalled);
+                }
+                (SEARCH_BAR, Cmd::None)
             }
+            // Recalls the next query in the history, or the one being typed
+            Event::Keyboard(KeyEvent {
+                code: Key::Down,
+                modifiers: KeyModifiers::NONE,
+            }) => {
+                let search_bar = children.get_mut(SEARCH_BAR).unwrap();
+                match self.recall {
+                    Some(0) => {
+                        self.recall = None;
+                        set_query(search_bar, std::mem::take(&mut self.draft));
+                    }
+                    Some(back) => {
+                        if let Some(recalled) = self.history.recall(back - 1) {
+                            self.recall = Some(back - 1);
+                            set_query(search_bar, recalled);
+                        }
+                    }
+                    None => (),
+                }
+                (SEARCH_BAR, Cmd::None)
+            }
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('l'),
+                modifiers: KeyModifiers::CONTROL,
+            }) => return Some(AppMsg::ClearSearchHistory),
             Event::Keyboard(KeyEvent {
                 code: Key::Delete, ..
             }) => (SEARCH_BAR, Cmd::Cancel),
@@ -92,6 +153,17 @@ impl Component<AppMsg, UserEvent> for TopBar {
                 modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
             }) => (SEARCH_BAR, Cmd::Type(ch)),
             Event::Keyboard(KeyEvent { code: Key::Tab, .. }) => return Some(AppMsg::GoNextItem),
+            // Searches again, skipping the cached results
+            Event::Keyboard(KeyEvent {
+                code: Key::Char('r'),
+                modifiers: KeyModifiers::CONTROL,
+            }) => {
+                let search_bar = children.get_mut(SEARCH_BAR).unwrap();
+                if let State::One(StateValue::String(query)) = search_bar.state() {
+                    return Some(AppMsg::RefreshQuery(query));
+                }
+                (SEARCH_BAR, Cmd::None)
+            }
             Event::Keyboard(KeyEvent {
                 code: Key::Enter, ..
             }) => {
@@ -101,16 +173,41 @@ impl Component<AppMsg, UserEvent> for TopBar {
                 }
                 (SEARCH_BAR, Cmd::None)
             }
-            Event::User(UserEvent::DownloadRegistered(song_name)) => {
-                let _ = self.tx.send(TrackInfo::New(song_name));
-                (DOWNLOAD_TRACKER, Cmd::Change)
+            Event::Tick => {
+                // The interface is redrawn only if the tracker has changed
+                let tracker = children.get_mut(DOWNLOAD_TRACKER).unwrap();
+                return match tracker.perform(Cmd::Change) {
+                    CmdResult::None => None,
+                    _ => Some(AppMsg::None),
+                };
             }
             _ => (SEARCH_BAR, Cmd::None),
         };
 
+        let is_search_bar = child == SEARCH_BAR;
+        let is_edit = matches!(cmd, Cmd::Type(_) | Cmd::Delete | Cmd::Cancel);
         let child = children.get_mut(child).unwrap();
         child.perform(cmd);
 
+        if is_search_bar {
+            // Editing a recalled query makes it a new one
+            if is_edit {
+                self.recall = None;
+            }
+
+            // Completions are looked for in the history first, then in the library
+            let typed = query(child.as_ref());
+            self.suggestion = if self.recall.is_none() && !typed.trim().is_empty() {
+                self.history
+                    .complete(&typed)
+                    .or_else(|| self.library.complete(&typed))
+            } else {
+                None
+            };
+            // Syntax errors are pointed out while typing
+            show_hint(child, self.suggestion.as_deref());
+        }
+
         Some(AppMsg::None)
     }
 }
@@ -1,9 +1,11 @@
 use tui_realm_stdlib::Input;
 use tuirealm::{
-    props::{Borders, Style, TextModifiers},
-    AttrValue, MockComponent,
+    props::{Alignment, Borders, Color, Style, TextModifiers},
+    AttrValue, Attribute, MockComponent, State, StateValue,
 };
 
+use crate::ui::search_query::SearchQuery;
+
 #[derive(MockComponent)]
 pub(super) struct SearchBar {
     component: Input,
@@ -31,3 +33,40 @@ impl Default for SearchBar {
         SearchBar { component: input }
     }
 }
+
+/// Returns the query typed in `search_bar`
+pub(super) fn query(search_bar: &dyn MockComponent) -> String {
+    match search_bar.state() {
+        State::One(StateValue::String(query)) => query,
+        _ => String::new(),
+    }
+}
+
+/// Replaces the query typed in `search_bar`, moving the cursor at its end
+pub(super) fn set_query(search_bar: &mut Box<dyn MockComponent>, query: String) {
+    search_bar.attr(Attribute::Value, AttrValue::String(query));
+}
+
+/// Shows in the title of `search_bar` what's wrong with the query typed,
+/// if it's malformed, or else the `suggestion` completing it, if any
+pub(super) fn show_hint(search_bar: &mut Box<dyn MockComponent>, suggestion: Option<&str>) {
+    let error = SearchQuery::parse(&query(search_bar.as_ref())).err();
+
+    let (title, color) = match (error, suggestion) {
+        (Some(error), _) => (
+            format!("{}, searching as plain text", error),
+            Color::LightRed,
+        ),
+        (None, Some(suggestion)) => (format!("END to complete: {}", suggestion), Color::Reset),
+        (None, None) => (String::new(), Color::Reset),
+    };
+    search_bar.attr(Attribute::Title, AttrValue::Title((title, Alignment::Left)));
+    search_bar.attr(
+        Attribute::Borders,
+        AttrValue::Borders(
+            Borders::default()
+                .sides(tuirealm::props::BorderSides::all())
+                .color(color),
+        ),
+    );
+}